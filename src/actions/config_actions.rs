@@ -0,0 +1,101 @@
+use std::fs::{read_to_string, DirBuilder};
+
+use crate::config::{check, migrate_legacy, ConfigFile};
+use crate::error::{Error, Result};
+use crate::utils::index::ProjectIndex;
+use crate::utils::{get_config_path, ActionTrait, HelpTrait};
+use crate::Config;
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigCommand {
+    Migrate,
+    Check,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigAction {
+    pub command: Option<ConfigCommand>,
+    pub help: bool,
+}
+
+impl HelpTrait for ConfigAction {
+    fn print_help(&self) {
+        println!(
+            "op config migrate             : Converts `.opconfig` to `op.toml` in config directory"
+        );
+        println!("op config check               : Reports every problem in config with its line number, exits non-zero if any");
+    }
+}
+
+impl ConfigAction {
+    fn migrate(&self) -> Result<()> {
+        let legacy_path = get_config_path()?;
+        let toml_path = ConfigFile::toml_path()?;
+        if toml_path.exists() {
+            return Err(Error::Any(format!(
+                "'{}' already exists. Remove it to migrate again",
+                toml_path.display()
+            )));
+        }
+        if !legacy_path.exists() {
+            return Err(Error::Any(format!(
+                "Nothing to migrate, '{}' doesn't exist",
+                legacy_path.display()
+            )));
+        }
+
+        let output = migrate_legacy(&read_to_string(&legacy_path)?);
+        if let Some(parent) = toml_path.parent() {
+            DirBuilder::new().recursive(true).create(parent)?;
+        }
+        std::fs::write(&toml_path, output)?;
+        println!(
+            "Migrated '{}' to '{}'",
+            legacy_path.display(),
+            toml_path.display()
+        );
+        println!("'{}' is no longer read", legacy_path.display());
+        Ok(())
+    }
+}
+
+impl ConfigAction {
+    fn check(&self) -> Result<()> {
+        let config_file = ConfigFile::locate()?;
+        let path = config_file.path().display();
+        if !config_file.path().exists() {
+            println!("No config found at '{path}', defaults are used");
+            return Ok(());
+        }
+
+        let entries = match config_file.read_entries() {
+            Ok(entries) => entries,
+            Err(err) => {
+                println!("{err}");
+                return Err(Error::InvalidConfig(1));
+            }
+        };
+        let diagnostics = check(&entries, &mut ProjectIndex::load())?;
+        for diagnostic in &diagnostics {
+            println!("{path}:{}: {}", diagnostic.line, diagnostic.message);
+        }
+        if !diagnostics.is_empty() {
+            return Err(Error::InvalidConfig(diagnostics.len()));
+        }
+        println!("No problems found in '{path}'");
+        Ok(())
+    }
+}
+
+impl ActionTrait for ConfigAction {
+    fn execute(&self, _config: Config) -> Result<()> {
+        match self.command {
+            Some(ConfigCommand::Migrate) if !self.help => self.migrate(),
+            Some(ConfigCommand::Check) if !self.help => self.check(),
+            _ => {
+                self.print_help();
+                Ok(())
+            }
+        }
+    }
+}
@@ -1,57 +1,113 @@
 use std::fs::DirBuilder;
+use std::path::Path;
 
 use crate::error::Result;
+use crate::utils::constants::DEFAULT_LANGUAGES;
+use crate::utils::validate_dir_name;
 use crate::utils::ActionTrait;
 use crate::utils::HelpTrait;
 use crate::Config;
 
 #[derive(Debug, PartialEq)]
-pub struct CreateLayout<'a> {
-    lang_types: Vec<&'a str>,
+pub struct CreateLayout {
+    // languages given on command line, `languages` from config otherwise
+    pub lang_types: Vec<String>,
+    // only prints what would be created
+    pub dry_run: bool,
+    // create the layout in extra roots too
+    pub extra_roots: bool,
     pub help: bool,
 }
 
-impl CreateLayout<'_> {
+impl CreateLayout {
     pub fn new() -> Self {
         Self {
-            lang_types: vec!["python", "javascript", "rust", "go", "plain_txt"],
+            lang_types: Vec::new(),
+            dry_run: false,
+            extra_roots: false,
             help: false,
         }
     }
-    fn create_lang_dirs(&self, config: Config) -> Result<()> {
-        let path = config.projects_root;
-        let recurse = !path.exists();
-        if recurse {
-            println!("Creating directory '{}'", path.to_string_lossy());
+
+    fn create_dir(&self, path: &Path) -> Result<()> {
+        if !self.dry_run {
+            DirBuilder::new().recursive(true).create(path)?;
+        }
+        Ok(())
+    }
+
+    fn create_lang_dirs(&self, root: &Path, langs: &[String]) -> Result<()> {
+        let creating = if self.dry_run {
+            "Would create"
+        } else {
+            "Creating"
+        };
+        if !root.exists() {
+            println!("{creating} directory '{}'", root.to_string_lossy());
+            self.create_dir(root)?;
         }
         // create lang dirs
-        for lang in &self.lang_types {
-            let path = path.join(lang);
+        for lang in langs {
+            let path = root.join(lang);
             if path.exists() {
                 println!("'{lang}' sub-directory already exists. SKIPPING");
             } else {
-                println!("Creating sub-directory'{lang}'");
-                DirBuilder::new().recursive(recurse).create(path)?;
+                println!("{creating} sub-directory '{lang}'");
+                self.create_dir(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn create_layout(&self, config: Config) -> Result<()> {
+        let langs = match self.lang_types.is_empty() {
+            true => config.layout_languages(),
+            false => self.lang_types.clone(),
+        };
+        // checked up front so nothing is created for a bad list
+        for lang in &langs {
+            validate_dir_name("language", lang)?;
+        }
+
+        let mut roots = vec![&config.projects_root];
+        if self.extra_roots {
+            roots.extend(&config.extra_roots);
+        }
+        for (idx, root) in roots.into_iter().enumerate() {
+            if idx > 0 {
+                println!();
             }
+            println!("In '{}'", root.to_string_lossy());
+            self.create_lang_dirs(root, &langs)?;
         }
         println!("Done");
         Ok(())
     }
 }
 
-impl HelpTrait for CreateLayout<'_> {
+impl HelpTrait for CreateLayout {
     fn print_help(&self) {
-        println!("op --create|-c : Creates Projects->language layout in home directory\n");
-        println!("- Dirs for languages {:?} will be created", self.lang_types);
+        println!(
+            "op --create|-c                : Creates Projects->language layout in home directory"
+        );
+        println!("op --create|-c <lang...>      : Creates only the given language dirs");
+        println!("op --create|-c --dry-run|-d   : Shows dirs which would be created & skipped");
+        println!(
+            "op --create|-c --extra-roots|-e : Creates the layout in extra projects roots too\n"
+        );
+        println!(
+            "- Dirs for languages in `languages` of config are created, defaults to {:?}",
+            DEFAULT_LANGUAGES
+        );
     }
 }
 
-impl ActionTrait for CreateLayout<'_> {
+impl ActionTrait for CreateLayout {
     fn execute(&self, config: Config) -> Result<()> {
         if self.help {
             self.print_help();
         } else {
-            return self.create_lang_dirs(config);
+            return self.create_layout(config);
         }
         Ok(())
     }
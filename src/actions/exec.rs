@@ -0,0 +1,146 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::error::{Error, Result};
+use crate::utils::fuzzy::scored_fuzzy_search;
+use crate::utils::projects::Projects;
+use crate::utils::runner::{JobResult, Runner};
+use crate::utils::{get_projects, ActionTrait, HelpTrait};
+use crate::Config;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ExecAction {
+    // fuzzy matched against project ids
+    pub filter: Option<String>,
+    // `language_dir` of projects, like `rust` in the layout
+    pub lang: Option<String>,
+    // defaults to the number of CPUs
+    pub jobs: Option<usize>,
+    pub command: Vec<String>,
+    pub help: bool,
+}
+
+impl HelpTrait for ExecAction {
+    fn print_help(&self) {
+        println!("op exec -- <cmd...>           : Runs a command in every project");
+        println!("op exec --filter|-f <fuzzy>   : Only in projects fuzzy matching the filter");
+        println!(
+            "op exec --lang|-l <dir>       : Only in projects inside a `language_dir` like rust"
+        );
+        println!("op exec --jobs|-j <n>         : Number of projects worked on at a time, defaults to number of CPUs");
+        println!("A single <cmd> argument is run by the shell, e.g op exec -- 'git log -1 | cat'");
+    }
+}
+
+impl ExecAction {
+    /// Whether the command is run in the project at `path`. Languages are the
+    /// same as `@lang` in select UI, so nested projects are in one too
+    pub fn matches(&self, projects: &Projects, path: &PathBuf) -> bool {
+        if let Some(filter) = &self.filter {
+            let id = projects.project_id(path);
+            if !scored_fuzzy_search(&filter.to_lowercase(), &id.to_lowercase()).0 {
+                return false;
+            }
+        }
+        if let Some(lang) = &self.lang {
+            if projects.project_lang(path).as_ref() != Some(lang) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Program & its args. A single argument is a script run by the shell
+    fn program(&self) -> (String, Vec<String>) {
+        match &self.command[..] {
+            [script] if cfg!(windows) => ("cmd".to_owned(), vec!["/C".to_owned(), script.clone()]),
+            [script] => ("sh".to_owned(), vec!["-c".to_owned(), script.clone()]),
+            [program, args @ ..] => (program.clone(), args.to_vec()),
+            [] => unreachable!("command is checked while parsing args"),
+        }
+    }
+}
+
+fn print_prefixed(id: &str, output: &[u8], mut out: impl Write) {
+    for line in String::from_utf8_lossy(output).lines() {
+        let _ = writeln!(out, "{id} | {line}");
+    }
+}
+
+/// Exit code of the command in a project, `None` when killed by a signal or
+/// Ctrl-C and an error when it couldn't be run at all
+type ExecResult = std::result::Result<Option<i32>, String>;
+
+impl ActionTrait for ExecAction {
+    fn execute(&self, config: Config) -> Result<()> {
+        if self.help {
+            self.print_help();
+            return Ok(());
+        }
+        let projects = get_projects(config)?;
+        let targets = projects
+            .dir_items
+            .iter()
+            .filter(|path| self.matches(&projects, path))
+            .map(|path| (projects.project_id(path), path.to_owned()))
+            .collect::<Vec<(String, PathBuf)>>();
+        if targets.is_empty() {
+            return Err(Error::NoProjectsFound);
+        }
+
+        let (program, args) = self.program();
+        let runner = Runner {
+            jobs: self.jobs.unwrap_or(Runner::default().jobs),
+            ..Default::default()
+        };
+        let ids = targets.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+        let results = runner.run(targets, |(id, path), job| -> ExecResult {
+            let mut command = Command::new(&program);
+            command.args(&args).current_dir(&path);
+            match job.output(&mut command) {
+                Ok(Some(output)) => {
+                    // whole output of a project is printed at once so it isn't
+                    // interleaved with other projects
+                    print_prefixed(&id, &output.stdout, std::io::stdout().lock());
+                    print_prefixed(&id, &output.stderr, std::io::stderr().lock());
+                    Ok(output.status.code())
+                }
+                Ok(None) => Ok(None),
+                Err(err) => Err(err.to_string()),
+            }
+        })?;
+
+        let mut results = ids.into_iter().zip(results).collect::<Vec<_>>();
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        println!();
+        let mut failed = 0;
+        let mut cancelled = false;
+        for (id, result) in &results {
+            let status = match result {
+                JobResult::Done(Ok(Some(0))) => continue,
+                JobResult::Done(Ok(Some(code))) => format!("exited with {code}"),
+                JobResult::Done(Ok(None)) => "killed by a signal".to_owned(),
+                JobResult::Done(Err(err)) => format!("failed to run, {err}"),
+                JobResult::TimedOut => "timed out".to_owned(),
+                JobResult::Cancelled => {
+                    cancelled = true;
+                    "cancelled".to_owned()
+                }
+            };
+            failed += 1;
+            println!("{id:<25}: {status}");
+        }
+        println!(
+            "exec: {} succeeded, {failed} failed",
+            results.len() - failed
+        );
+        if cancelled {
+            return Err(Error::Cancelled);
+        }
+        if failed > 0 {
+            return Err(Error::Any(format!("command failed in {failed} project(s)")));
+        }
+        Ok(())
+    }
+}
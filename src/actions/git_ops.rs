@@ -0,0 +1,269 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::actions::git_status::{git_status, Operation, Status};
+use crate::error::{Error, Result};
+use crate::utils::runner::{Job, JobResult, Runner};
+use crate::utils::{get_projects, ActionTrait, HelpTrait};
+use crate::Config;
+
+pub const DEFAULT_GIT_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GitCommand {
+    Fetch,
+    // fast forward only
+    Pull,
+    Push,
+}
+
+impl GitCommand {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fetch" => Some(Self::Fetch),
+            "pull" => Some(Self::Pull),
+            "push" => Some(Self::Push),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fetch => "fetch",
+            Self::Pull => "pull",
+            Self::Push => "push",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GitAction {
+    pub command: Option<GitCommand>,
+    // max repos worked on at a time, defaults to the number of CPUs
+    pub jobs: Option<usize>,
+    // per repo, a repo taking longer is reported as failed
+    pub timeout: Duration,
+    pub help: bool,
+}
+
+impl Default for GitAction {
+    fn default() -> Self {
+        Self {
+            command: None,
+            jobs: None,
+            timeout: Duration::from_secs(DEFAULT_GIT_TIMEOUT_SECS),
+            help: false,
+        }
+    }
+}
+
+impl HelpTrait for GitAction {
+    fn print_help(&self) {
+        println!("op git fetch                  : Fetches all git projects");
+        println!("op git pull                   : Fetches & fast forwards all git projects. Dirty or diverged ones are skipped");
+        println!("op git push                   : Pushes all git projects ahead of upstream. Diverged ones are skipped");
+        println!("op git <cmd> --jobs|-j <n>    : Number of projects worked on at a time, defaults to number of CPUs");
+        println!("op git <cmd> --timeout|-t <s> : Seconds after which a project is given up on, defaults to {DEFAULT_GIT_TIMEOUT_SECS}");
+    }
+}
+
+/// Result of running a git command in a single project
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Done(String),
+    Skipped(String),
+    Failed(String),
+}
+
+impl Outcome {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Done(_) => "OK",
+            Self::Skipped(_) => "SKIPPED",
+            Self::Failed(_) => "FAILED",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            Self::Done(msg) | Self::Skipped(msg) | Self::Failed(msg) => msg,
+        }
+    }
+}
+
+struct Repo<'a> {
+    path: PathBuf,
+    job: &'a Job,
+}
+
+impl Repo<'_> {
+    /// Runs git until the job times out or is cancelled. Output of a failed
+    /// command is returned as the error
+    fn git(&self, args: &[&str]) -> std::result::Result<String, String> {
+        let mut git = Command::new("git");
+        git.arg("-C")
+            .arg(&self.path)
+            .args(args)
+            // a credential prompt would otherwise wait until timeout
+            .env("GIT_TERMINAL_PROMPT", "0");
+        match self.job.output(&mut git) {
+            Ok(Some(output)) if output.status.success() => {
+                Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+            }
+            Ok(Some(output)) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let reason = stderr.lines().rev().find(|l| !l.trim().is_empty());
+                Err(reason
+                    .unwrap_or("git exited with an error")
+                    .trim()
+                    .to_owned())
+            }
+            Ok(None) if self.job.is_cancelled() => Err("cancelled".to_owned()),
+            Ok(None) => Err("timed out".to_owned()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    fn status(&self) -> std::result::Result<Status, String> {
+        git_status(&self.path, self.job).ok_or("unable to read git status".to_owned())
+    }
+}
+
+pub fn skip_reason(status: &Status) -> Option<String> {
+    if status.branch.is_none() {
+        return Some("detached HEAD".to_owned());
+    }
+    if status.upstream.is_none() {
+        return Some("no upstream".to_owned());
+    }
+    match status.operation {
+        Operation::Merge => Some("merge in progress".to_owned()),
+        Operation::Rebase => Some("rebase in progress".to_owned()),
+        Operation::None => None,
+    }
+}
+
+fn fetch(repo: &Repo) -> Outcome {
+    match repo.git(&["fetch", "--quiet"]) {
+        Ok(_) => Outcome::Done("fetched".to_owned()),
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+fn pull(repo: &Repo, status: &Status) -> Outcome {
+    if let Some(reason) = skip_reason(status) {
+        return Outcome::Skipped(reason);
+    }
+    if status.is_dirty() {
+        return Outcome::Skipped("dirty".to_owned());
+    }
+    if let Err(err) = repo.git(&["fetch", "--quiet"]) {
+        return Outcome::Failed(err);
+    }
+    // ahead/behind are only known after fetching
+    let status = match repo.status() {
+        Ok(status) => status,
+        Err(err) => return Outcome::Failed(err),
+    };
+    match (status.ahead, status.behind) {
+        (_, 0) => Outcome::Done("up to date".to_owned()),
+        (0, behind) => match repo.git(&["merge", "--ff-only", "--quiet", "@{upstream}"]) {
+            Ok(_) => Outcome::Done(format!("fast forwarded {behind} commit(s)")),
+            Err(err) => Outcome::Failed(err),
+        },
+        (ahead, behind) => Outcome::Skipped(format!("diverged, ahead {ahead} & behind {behind}")),
+    }
+}
+
+fn push(repo: &Repo, status: &Status) -> Outcome {
+    if let Some(reason) = skip_reason(status) {
+        return Outcome::Skipped(reason);
+    }
+    match (status.ahead, status.behind) {
+        (0, _) => Outcome::Done("nothing to push".to_owned()),
+        (ahead, 0) => match repo.git(&["push", "--quiet"]) {
+            Ok(_) => Outcome::Done(format!("pushed {ahead} commit(s)")),
+            Err(err) => Outcome::Failed(err),
+        },
+        (ahead, behind) => Outcome::Skipped(format!("diverged, ahead {ahead} & behind {behind}")),
+    }
+}
+
+/// Runs `command` in the repository at `path`. `None` if it isn't one
+pub fn run_git_command(command: GitCommand, path: &Path, job: &Job) -> Option<Outcome> {
+    let status = git_status(path, job)?;
+    let repo = Repo {
+        path: path.to_owned(),
+        job,
+    };
+    let outcome = match command {
+        GitCommand::Fetch => fetch(&repo),
+        GitCommand::Pull => pull(&repo, &status),
+        GitCommand::Push => push(&repo, &status),
+    };
+    Some(outcome)
+}
+
+fn print_summary(command: GitCommand, outcomes: &[(String, Outcome)]) -> usize {
+    for (id, outcome) in outcomes {
+        println!("{:<25}: {:<8} {}", id, outcome.label(), outcome.message());
+    }
+    let count = |label: &str| outcomes.iter().filter(|(_, o)| o.label() == label).count();
+    let failed = count("FAILED");
+    println!(
+        "\n{}: {} succeeded, {} skipped, {} failed",
+        command.as_str(),
+        count("OK"),
+        count("SKIPPED"),
+        failed
+    );
+    failed
+}
+
+impl ActionTrait for GitAction {
+    fn execute(&self, config: Config) -> Result<()> {
+        let Some(command) = self.command.filter(|_| !self.help) else {
+            self.print_help();
+            return Ok(());
+        };
+        let projects = get_projects(config)?;
+        let default = Runner::default();
+        let runner = Runner {
+            jobs: self.jobs.unwrap_or(default.jobs),
+            timeout: Some(self.timeout),
+            progress: Some(format!("git {}", command.as_str())),
+        };
+        let results = runner.run(projects.dir_items.clone(), |path, job| {
+            run_git_command(command, &path, job)
+        })?;
+
+        let mut cancelled = false;
+        let mut outcomes = Vec::new();
+        for (path, result) in projects.dir_items.iter().zip(results) {
+            let outcome = match result {
+                JobResult::Done(Some(outcome)) => outcome,
+                // not a git repository
+                JobResult::Done(None) => continue,
+                JobResult::TimedOut => Outcome::Failed("timed out".to_owned()),
+                JobResult::Cancelled => {
+                    cancelled = true;
+                    Outcome::Skipped("cancelled".to_owned())
+                }
+            };
+            outcomes.push((projects.project_id(path), outcome));
+        }
+        outcomes.sort_by(|a, b| a.0.cmp(&b.0));
+        let failed = print_summary(command, &outcomes);
+        if cancelled {
+            return Err(Error::Cancelled);
+        }
+        if failed > 0 {
+            return Err(Error::Any(format!(
+                "git {} failed for {failed} project(s)",
+                command.as_str()
+            )));
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,49 @@
 use crate::error::{Error, Result};
+use crate::utils::output::{print_records, Field, OutputFormat, Record};
+use crate::utils::runner::{Job, JobResult, Runner};
 use crate::utils::{get_projects, ActionTrait, HelpTrait};
 use crate::Config;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use std::time::Duration;
 use std::{
-    path::PathBuf,
-    process::{Command, Stdio},
+    path::{Path, PathBuf},
+    process::Command,
 };
 
+// a status slower than this is most likely stuck on a network drive
+const GIT_STATUS_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Debug, PartialEq, Default)]
 pub struct GitStatusAction {
+    pub format: OutputFormat,
+    // filters, a project is shown if it matches any of the set ones
+    pub dirty: bool,
+    pub ahead: bool,
+    pub behind: bool,
     pub help: bool,
 }
 impl HelpTrait for GitStatusAction {
     fn print_help(&self) {
-        println!("op --git-status|-g            : Shows uncommitted and non-sync status of all projects. Ignores git uninitiated or clean");
+        println!("op --git-status|-g            : Shows branch, sync & worktree status of all projects. Ignores git uninitiated or clean & in sync");
+        println!("op --git-status|-g --dirty|-d : Only shows projects with staged, modified or untracked files");
+        println!("op --git-status|-g --ahead|-a : Only shows projects with commits not pushed to upstream");
+        println!("op --git-status|-g --behind|-b: Only shows projects with commits not pulled from upstream");
+        println!("op --git-status|-g --format|-f json|tsv");
+        println!("                              : Prints status of all git projects");
+    }
+}
+impl GitStatusAction {
+    fn is_filtered(&self) -> bool {
+        self.dirty || self.ahead || self.behind
+    }
+
+    fn show(&self, status: &Status) -> bool {
+        if !self.is_filtered() {
+            // everything is shown for scripts, only notable ones for humans
+            return self.format != OutputFormat::Text || status.is_notable();
+        }
+        (self.dirty && status.is_dirty())
+            || (self.ahead && status.ahead > 0)
+            || (self.behind && status.behind > 0)
     }
 }
 impl ActionTrait for GitStatusAction {
@@ -23,10 +52,43 @@ impl ActionTrait for GitStatusAction {
             self.print_help();
         } else {
             let projects = get_projects(config)?;
-            let all_git_projs = gitstatus_on_multiple_threads(projects.dir_items)?;
-            for proj in all_git_projs {
-                show_output(proj);
+            let runner = Runner {
+                timeout: Some(GIT_STATUS_TIMEOUT),
+                progress: Some("git status".to_owned()),
+                ..Default::default()
+            };
+            let results = runner.run(projects.dir_items.clone(), |path, job| {
+                git_status(&path, job).map(|status| GitProject { path, status })
+            })?;
+
+            let mut git_projs = Vec::new();
+            for (path, result) in projects.dir_items.iter().zip(results) {
+                match result {
+                    JobResult::Done(Some(proj)) if self.show(&proj.status) => git_projs.push(proj),
+                    JobResult::Done(_) => {}
+                    JobResult::TimedOut => {
+                        let id = projects.project_id(path);
+                        eprintln!("WARNING: git status timed out for {id}");
+                    }
+                    JobResult::Cancelled => return Err(Error::Cancelled),
+                }
+            }
+
+            if self.format == OutputFormat::Text {
+                for proj in git_projs {
+                    show_output(&projects.project_id(&proj.path), &proj.status);
+                }
+                return Ok(());
             }
+            let records = git_projs
+                .iter()
+                .map(|proj| {
+                    let mut record = projects.project_record(&proj.path);
+                    record.extend(proj.status.record());
+                    record
+                })
+                .collect::<Vec<_>>();
+            print_records(self.format, &GIT_RECORD_KEYS, &records);
         }
         Ok(())
     }
@@ -38,188 +100,226 @@ impl ActionTrait for GitStatusAction {
 #[derive(Debug)]
 pub struct GitProject {
     path: PathBuf,
-    branch: Branch,
+    status: Status,
 }
 
-#[derive(Debug)]
-pub struct Branch {
-    local_clean_worktree: bool,
-    ahead_of_remote: i32,
-    behind_of_remote: i32,
+/// Merge or rebase which was started but not finished yet
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Operation {
+    #[default]
+    None,
+    Merge,
+    Rebase,
 }
 
-fn parse_git_status_output(status_line: &'_ str, count: usize) -> Option<Branch> {
-    // parse output of first line of git status -b -s
-    // ## main...remotes/origin/main [ahead 1, behind 2]
-    let (_ignore_bangs, branch_details) = status_line.split_once(' ')?;
-    if branch_details.starts_with("No commits") {
-        return None;
-    }
-    let mut ahead_of_remote = 0;
-    let mut behind_of_remote = 0;
-    let local_clean_worktree = count == 0;
-
-    if let Some((_branch_name, branch_status)) = branch_details.split_once(' ') {
-        let mut buf = String::new();
-        let mut is_ahead = true;
-        for ch in branch_status.chars() {
-            match ch {
-                '[' => {}
-                ']' | ',' => {
-                    if is_ahead {
-                        ahead_of_remote = buf.parse().unwrap_or_default();
-                    }
-                    behind_of_remote = buf.parse().unwrap_or_default();
-                    buf.clear();
-                }
-                ' ' => {
-                    if buf == "behind" {
-                        is_ahead = false;
-                    }
-                    buf.clear();
-                }
-                _ => {
-                    buf.push(ch);
-                }
-            }
+impl Operation {
+    fn as_str(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Merge => Some("merge"),
+            Self::Rebase => Some("rebase"),
         }
     }
+}
 
-    Some(Branch {
-        local_clean_worktree,
-        ahead_of_remote,
-        behind_of_remote,
-    })
+#[derive(Debug, Default, PartialEq)]
+pub struct Status {
+    // `None` when HEAD is detached
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: i64,
+    pub behind: i64,
+    pub staged: i64,
+    // modified in worktree, unmerged files included
+    pub modified: i64,
+    pub untracked: i64,
+    pub stash: i64,
+    pub operation: Operation,
 }
 
-fn run_git_status(path: &PathBuf) -> Option<GitProject> {
-    let mut git = Command::new("git");
-    git.arg("-C")
-        .arg(path)
-        .arg("status")
-        .arg("--branch")
-        .arg("--short");
-    let output = git
-        .stdout(Stdio::piped())
-        .output()
-        .expect("git should be installed");
-    if let Some(code) = output.status.code() {
-        if code == 0 {
-            // success case
-            let command_output = String::from_utf8_lossy(&output.stdout);
-            let mut lines = command_output.lines();
-            let status_line = lines.next()?;
-            let remaning_lines = lines.count();
-            let branch = parse_git_status_output(status_line, remaning_lines);
-            if let Some(branch) = branch {
-                return Some(GitProject {
-                    path: path.to_owned(),
-                    branch,
-                });
-            }
-            return None;
-        };
+/// Fields of every record in machine readable git status output
+const GIT_RECORD_KEYS: [&str; 15] = [
+    "name",
+    "path",
+    "source",
+    "id",
+    "branch",
+    "upstream",
+    "ahead",
+    "behind",
+    "dirty",
+    "staged",
+    "modified",
+    "untracked",
+    "stash",
+    "detached",
+    "operation",
+];
+
+impl Status {
+    fn changed_files(&self) -> i64 {
+        self.staged + self.modified + self.untracked
     }
-    None
-}
 
-fn show_output(proj: Option<GitProject>) {
-    // only show an output if current local and remote are out of sync or
-    // has uncommitted local changes
-    if let Some(proj) = proj {
-        let file_name = proj
-            .path
-            .file_name()
-            .expect("Unable to covert to OsStr")
-            .to_str()
-            .expect("Unable to convert OsStr to &str");
-        let mut proj_status: Vec<&str> = Vec::new();
-        if proj.branch.ahead_of_remote != 0 && proj.branch.behind_of_remote != 0 {
-            proj_status.push("NOT IN SYNC");
-        }
-        if !proj.branch.local_clean_worktree {
-            proj_status.push("DIRTY");
+    pub fn is_dirty(&self) -> bool {
+        self.changed_files() > 0
+    }
+
+    fn is_notable(&self) -> bool {
+        self.is_dirty()
+            || self.ahead > 0
+            || self.behind > 0
+            || self.stash > 0
+            || self.branch.is_none()
+            || self.operation != Operation::None
+    }
+
+    /// Branch & its upstream, like `main -> origin/main`
+    pub fn branch_summary(&self) -> String {
+        match (&self.branch, &self.upstream) {
+            (Some(branch), Some(upstream)) => format!("{branch} -> {upstream}"),
+            (Some(branch), None) => branch.to_owned(),
+            (None, _) => "HEAD (detached)".to_owned(),
         }
-        if !proj_status.is_empty() {
-            println!("{:<25}: {:?}", file_name, proj_status);
+    }
+
+    /// Non zero counts & an in progress operation, like `ahead 1`
+    pub fn details(&self) -> Vec<String> {
+        let counts = [
+            ("ahead", self.ahead),
+            ("behind", self.behind),
+            ("staged", self.staged),
+            ("modified", self.modified),
+            ("untracked", self.untracked),
+            ("stash", self.stash),
+        ];
+        let mut details = counts
+            .iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(name, count)| format!("{name} {count}"))
+            .collect::<Vec<_>>();
+        if let Some(operation) = self.operation.as_str() {
+            details.push(format!("{} in progress", operation.to_uppercase()));
         }
+        details
     }
-}
 
-////////////////////////////////////////////////////////////////////////////////
-// thread pool and worker
-////////////////////////////////////////////////////////////////////////////////
-struct Worker {
-    thread: Option<thread::JoinHandle<()>>,
+    fn record(&self) -> Record {
+        vec![
+            ("branch", Field::from(self.branch.as_deref())),
+            ("upstream", Field::from(self.upstream.as_deref())),
+            ("ahead", Field::from(self.ahead)),
+            ("behind", Field::from(self.behind)),
+            ("dirty", Field::from(self.changed_files())),
+            ("staged", Field::from(self.staged)),
+            ("modified", Field::from(self.modified)),
+            ("untracked", Field::from(self.untracked)),
+            ("stash", Field::from(self.stash)),
+            ("detached", Field::from(self.branch.is_none())),
+            ("operation", Field::from(self.operation.as_str())),
+        ]
+    }
 }
 
-type AcMx<T> = Arc<Mutex<T>>;
-
-impl Worker {
-    fn job(
-        _id: usize,
-        input_data: AcMx<Vec<PathBuf>>,
-        output_data: AcMx<Vec<Option<GitProject>>>,
-    ) -> Self {
-        // TLDR: values from `input_data` are popped, processed and the output
-        // is pushed to `output_data`
-        let thread = thread::spawn(move || loop {
-            let mut data = input_data
-                .lock()
-                .expect("Unable to acquire lock on input_data");
-            let o_path = data.pop();
-            drop(data);
-            match o_path {
-                Some(path) => {
-                    let res = run_git_status(&path);
-                    let mut locked_output = output_data
-                        .lock()
-                        .expect("Unable to acquire lock on output_data");
-                    locked_output.push(res);
+/// Parses output of `git status --porcelain=v2 --branch --show-stash`
+/// # branch.oid <commit> | (initial)
+/// # branch.head <branch> | (detached)
+/// # branch.upstream <upstream_branch>
+/// # branch.ab +<ahead> -<behind>
+/// # stash <count>
+/// 1 <XY> ...
+/// 2 <XY> ...
+/// u <XY> ...
+/// ? <path>
+pub fn parse_git_status_output(output: &str) -> Status {
+    let mut status = Status::default();
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_owned()),
+                "branch.upstream" => status.upstream = Some(value.to_owned()),
+                "branch.ab" => {
+                    for count in value.split(' ') {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or_default();
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or_default();
+                        }
+                    }
+                }
+                "stash" => status.stash = value.parse().unwrap_or_default(),
+                _ => {}
+            }
+            continue;
+        }
+        let mut parts = line.splitn(3, ' ');
+        match (parts.next(), parts.next()) {
+            (Some("1" | "2"), Some(xy)) => {
+                let mut xy = xy.chars();
+                if xy.next().is_some_and(|x| x != '.') {
+                    status.staged += 1;
                 }
-                None => {
-                    break;
+                if xy.next().is_some_and(|y| y != '.') {
+                    status.modified += 1;
                 }
             }
-        });
-        Self {
-            thread: Some(thread),
+            (Some("u"), _) => status.modified += 1,
+            (Some("?"), _) => status.untracked += 1,
+            _ => {}
         }
     }
+    status
 }
-struct Executor;
-
-impl Executor {
-    fn run(num_threads: usize, data: Vec<PathBuf>) -> AcMx<Vec<Option<GitProject>>> {
-        let mut workers = Vec::<Worker>::with_capacity(num_threads);
-        let input_data = Arc::new(Mutex::new(data));
-
-        let git_data: Vec<Option<GitProject>> = Vec::new();
-        let atomic_git_output = Arc::new(Mutex::new(git_data));
-
-        // spawn threads which will work until values in `input_data` are exhausted
-        for id in 0..num_threads {
-            workers.push(Worker::job(
-                id,
-                input_data.clone(),
-                atomic_git_output.clone(),
-            ));
-        }
 
-        for worker in &mut workers {
-            if let Some(t) = worker.thread.take() {
-                t.join().expect("Unable to join thread to parent thread");
-            }
-        }
+fn git_dir(path: &Path) -> Option<PathBuf> {
+    // `.git` is a file pointing to the actual git dir for worktrees & submodules
+    let dot_git = path.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let content = std::fs::read_to_string(&dot_git).ok()?;
+    let git_dir = content.trim().strip_prefix("gitdir:")?.trim();
+    Some(path.join(git_dir))
+}
+
+fn in_progress_operation(path: &Path) -> Operation {
+    let Some(git_dir) = git_dir(path) else {
+        return Operation::None;
+    };
+    if git_dir.join("rebase-merge").exists() || git_dir.join("rebase-apply").exists() {
+        Operation::Rebase
+    } else if git_dir.join("MERGE_HEAD").exists() {
+        Operation::Merge
+    } else {
+        Operation::None
+    }
+}
 
-        atomic_git_output
+/// Status of the git repository at `path`, `None` if it isn't one or git
+/// was stopped by `job`
+pub fn git_status(path: &Path, job: &Job) -> Option<Status> {
+    let mut git = Command::new("git");
+    git.arg("-C")
+        .arg(path)
+        .arg("status")
+        .arg("--porcelain=v2")
+        .arg("--branch")
+        .arg("--show-stash");
+    let output = job.output(&mut git).ok()??;
+    if !output.status.success() {
+        return None;
     }
+    let mut status = parse_git_status_output(&String::from_utf8_lossy(&output.stdout));
+    status.operation = in_progress_operation(path);
+    Some(status)
 }
 
-fn gitstatus_on_multiple_threads(paths: Vec<PathBuf>) -> Result<Vec<Option<GitProject>>> {
-    let num_threads = 6;
-    let pool = Executor::run(num_threads, paths);
-    let lock = Arc::into_inner(pool).ok_or(Error::GitStatus)?;
-    let output_data = lock.into_inner().map_err(|_| Error::GitStatus)?;
-    Ok(output_data)
+fn show_output(id: &str, status: &Status) {
+    println!(
+        "{:<25}: {:<30} {}",
+        id,
+        status.branch_summary(),
+        status.details().join(", ")
+    );
 }
@@ -1,14 +1,20 @@
 use crate::error::Result;
+use crate::utils::output::{print_records, OutputFormat};
+use crate::utils::projects::PROJECT_RECORD_KEYS;
 use crate::utils::{get_projects, ActionTrait, HelpTrait};
 use crate::Config;
 
 #[derive(Default, Debug, PartialEq)]
 pub struct ListAction {
+    pub format: OutputFormat,
     pub help: bool,
 }
 impl HelpTrait for ListAction {
     fn print_help(&self) {
-        println!("op --list|-l : Prints all available projects to stdout");
+        println!("op --list|-l                       : Prints all available projects to stdout");
+        println!(
+            "op --list|-l --format|-f json|tsv  : Prints name, path, source & id of all projects"
+        );
     }
 }
 
@@ -16,6 +22,9 @@ impl ActionTrait for ListAction {
     fn execute(&self, config: Config) -> Result<()> {
         if self.help {
             self.print_help();
+        } else if self.format != OutputFormat::Text {
+            let projects = get_projects(config)?;
+            print_records(self.format, &PROJECT_RECORD_KEYS, &projects.records());
         } else {
             let projects = get_projects(config)?.catch_empty_project_list()?;
             println!("{}", projects.display_fmt(0, projects.filtered_items.len()));
@@ -12,11 +12,31 @@ impl HelpTrait for MainHelpAction {
         println!(
             "op --create|-c                : Creates Projects->language layout in home directory"
         );
-        println!("op <project_name>             : Opens project directly in neovim");
+        println!("op --create|-c [<lang...>] [--dry-run|-d] [--extra-roots|-e]");
+        println!("                              : Creates given language dirs, previews or creates in extra roots too");
+        println!("op <project_name>             : Opens project directly in editor");
         println!("op <project_name> --print|-p  : Prints project path to stdout");
-        println!("op <project_name> --uri|-u    : Prints remote uri path to stdout");
-        println!("op --add|-a <path>            : Adds a path to `.opinclude`");
-        println!("op --git-status|-g            : Shows uncommitted and non-sync status of all projects. Ignores git uninitiated or clean");
+        println!("op --print|-p                 : Prints paths of projects chosen in select UI, tab marks several");
+        println!("op <project_name> --uri|-u    : Prints web url of the git remote to stdout");
+        println!("op <project_name> --uri|-u [--remote|-r <name> | --all-remotes|-a]");
+        println!(
+            "                              : Uses another remote than origin or lists every remote"
+        );
+        println!("op <project_name> --open-web|-w [--branch|-b | --pr|-m | --ci|-c] [--remote|-r <name>] [--launch|-l]");
+        println!("                              : Prints or opens web url of the repo, current branch, new pull request or CI");
+        println!("op new <lang> <name> [--template|-t <t>] [--git|-g]");
+        println!("                              : Creates a project from a template & opens it in editor");
+        println!("op --add|-a <path>            : Adds a path to includes in config");
+        println!("op --git-status|-g            : Shows branch, sync & worktree status of all projects. Filter with --dirty, --ahead, --behind");
+        println!("op <cmd> --format|-f json|tsv : Machine readable output for --list, --print & --git-status");
+        println!(
+            "op config migrate             : Converts `.opconfig` to `op.toml` in config directory"
+        );
+        println!("op config check               : Reports every problem in config with its line number, exits non-zero if any");
+        println!("op git fetch|pull|push        : Fetches, fast forwards or pushes all git projects in parallel");
+        println!("op exec [--filter|-f <fuzzy>] [--lang|-l <dir>] -- <cmd...>");
+        println!("                              : Runs a command in every (or matching) project");
+        println!("op --reindex|-r               : Rescans all project roots and rebuilds the cached project index");
     }
 }
 impl ActionTrait for MainHelpAction {
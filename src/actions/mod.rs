@@ -1,6 +1,12 @@
+pub mod config_actions;
 pub mod create_layout;
+pub mod exec;
+pub mod git_ops;
 pub mod git_status;
 pub mod list_projects;
 pub mod main_help;
+pub mod new_project;
 pub mod open_in_nvim;
 pub mod opinclude_actions;
+pub mod reindex;
+pub mod select_print;
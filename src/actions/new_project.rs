@@ -0,0 +1,166 @@
+use std::fs::{self, DirBuilder};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use walkdir::WalkDir;
+
+use crate::error::{Error, Result};
+use crate::utils::constants::OP_TEMPLATES;
+use crate::utils::{get_config_dir, get_projects, validate_dir_name, ActionTrait, HelpTrait};
+use crate::Config;
+
+const NAME_PLACEHOLDER: &str = "{{name}}";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct NewProjectAction {
+    // `language_dir` the project is created in
+    pub lang: String,
+    pub name: String,
+    // dir name in `templates` of the config dir
+    pub template: Option<String>,
+    // run `git init` in the new project
+    pub git: bool,
+    pub help: bool,
+}
+
+impl HelpTrait for NewProjectAction {
+    fn print_help(&self) {
+        println!("op new <lang> <name>                      : Creates projects_root/<lang>/<name> & opens it in editor");
+        println!("op new <lang> <name> --template|-t <name> : Copies a template from `templates` in config directory");
+        println!("op new <lang> <name> --git|-g             : Runs `git init` in the new project");
+        println!("{NAME_PLACEHOLDER} in names & contents of template files is replaced with project name");
+    }
+}
+
+/// Copies `template` dir to `target` replacing the name placeholder in file &
+/// dir names and contents of text files
+pub fn copy_template(template: &Path, target: &Path, name: &str) -> Result<()> {
+    let walker = WalkDir::new(template)
+        .min_depth(1)
+        .into_iter()
+        // a template kept in git shouldn't bring its history along
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry.map_err(|err| Error::Any(err.to_string()))?;
+        let relative = entry
+            .path()
+            .strip_prefix(template)
+            .expect("walked paths are inside template");
+        let dest = target.join(relative.to_string_lossy().replace(NAME_PLACEHOLDER, name));
+
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            DirBuilder::new().recursive(true).create(&dest)?;
+        } else if file_type.is_file() {
+            let content = fs::read(entry.path())?;
+            match String::from_utf8(content) {
+                Ok(text) => fs::write(&dest, text.replace(NAME_PLACEHOLDER, name))?,
+                // binary files are copied as is
+                Err(err) => fs::write(&dest, err.into_bytes())?,
+            }
+            // keeps scripts executable
+            fs::set_permissions(
+                &dest,
+                entry
+                    .metadata()
+                    .map_err(std::io::Error::from)?
+                    .permissions(),
+            )?;
+        } else {
+            eprintln!(
+                "WARNING: skipping '{}', only files & dirs are copied",
+                entry.path().display()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn git_init(path: &Path) -> Result<()> {
+    let status = Command::new("git")
+        .arg("init")
+        .arg("--quiet")
+        .current_dir(path)
+        .stdout(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(Error::Any(format!(
+            "git init failed in '{}'",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+impl NewProjectAction {
+    fn template_dir(&self) -> Result<Option<PathBuf>> {
+        let Some(template) = &self.template else {
+            return Ok(None);
+        };
+        validate_dir_name("template", template)?;
+        let dir = get_config_dir()?.join(OP_TEMPLATES).join(template);
+        if !dir.is_dir() {
+            return Err(Error::Any(format!(
+                "Template '{template}' not found, expected a directory at '{}'",
+                dir.display()
+            )));
+        }
+        Ok(Some(dir))
+    }
+
+    /// Creates the project dir and returns its path
+    fn create(&self, config: &Config) -> Result<PathBuf> {
+        validate_dir_name("language", &self.lang)?;
+        validate_dir_name("project", &self.name)?;
+        if !config.projects_root.exists() {
+            return Err(Error::NoProjectsFound);
+        }
+        let lang_dir = config.projects_root.join(&self.lang);
+        let path = lang_dir.join(&self.name);
+        if path.exists() {
+            return Err(Error::Any(format!("'{}' already exists", path.display())));
+        }
+        // checked before creating anything so a typo leaves nothing behind
+        let template = self.template_dir()?;
+
+        let new_lang_dir = !lang_dir.exists();
+        if new_lang_dir {
+            println!("Creating sub-directory '{}'", self.lang);
+        }
+        DirBuilder::new().recursive(true).create(&path)?;
+        if let Err(err) = self.fill(&path, template.as_deref()) {
+            // a failed project isn't left half made
+            let _ = fs::remove_dir_all(&path);
+            if new_lang_dir {
+                let _ = fs::remove_dir(&lang_dir);
+            }
+            return Err(err);
+        }
+        println!("Created '{}'", path.display());
+        Ok(path)
+    }
+
+    fn fill(&self, path: &Path, template: Option<&Path>) -> Result<()> {
+        if let Some(template) = template {
+            copy_template(template, path, &self.name)?;
+        }
+        if self.git {
+            git_init(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl ActionTrait for NewProjectAction {
+    fn execute(&self, config: Config) -> Result<()> {
+        if self.help {
+            self.print_help();
+            return Ok(());
+        }
+        let path = self.create(&config)?;
+        // opened by path, with `discovery = markers` a project without a
+        // marker isn't found
+        let projects = get_projects(config)?;
+        projects.open_path_in_editor(&path, &format!("{}/{}", self.lang, self.name))
+    }
+}
@@ -1,6 +1,11 @@
+use std::path::Path;
 use std::process::{Command, Stdio};
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::utils::output::{print_records, OutputFormat};
+use crate::utils::projects::PROJECT_RECORD_KEYS;
+use crate::utils::remote_url::{Forge, RemoteUrl, WebLink};
+use crate::utils::terminal::TermEnv;
 use crate::utils::{get_projects, ActionTrait, HelpTrait};
 use crate::Config;
 
@@ -9,53 +14,180 @@ pub struct OpAction {
     pub proj_name: String,
     pub print_path: bool,
     pub print_uri: bool,
+    // remote used by `--uri` & `--open-web`, defaults to `origin`
+    pub remote: Option<String>,
+    // print web urls of every remote
+    pub all_remotes: bool,
+    // page linked to by `--open-web`
+    pub web: Option<WebLink>,
+    // open the link with `xdg-open` instead of printing it
+    pub launch: bool,
+    pub format: OutputFormat,
     pub help: bool,
 }
 impl HelpTrait for OpAction {
     fn print_help(&self) {
         println!("Try to use one of the below commands \n");
-        println!("op <project_name>            : Opens project directly in neovim");
+        println!("op <project_name>            : Opens project directly in editor");
         println!("op <project_name> --print|-p : Prints project path to stdout");
-        println!("op <project_name> --uri|-u   : Prints remote uri path to stdout");
+        println!("op <project_name> --uri|-u   : Prints web url of the git remote to stdout");
+        println!("op <project_name> --uri|-u --remote|-r <name> : Uses the given remote instead of origin");
+        println!(
+            "op <project_name> --uri|-u --all-remotes|-a   : Prints every remote with its web url"
+        );
+        println!("op <project_name> --open-web|-w [--branch|-b | --pr|-m | --ci|-c] [--remote|-r <name>] [--launch|-l]");
+        println!("                             : Prints web url of the repo, current branch, a new pull request");
+        println!("                               for it or CI page. Opened with xdg-open when --launch is given");
+        println!("op <project_name> --print|-p --format|-f json|tsv");
+        println!("                             : Prints name, path, source & id of the project");
     }
 }
 impl ActionTrait for OpAction {
     fn execute(&self, config: Config) -> Result<()> {
         if self.help {
             self.print_help();
+        } else if self.print_path && self.format != OutputFormat::Text {
+            let projects = get_projects(config)?;
+            let records = projects.matching_records(&self.proj_name)?;
+            if records.is_empty() {
+                eprintln!("No matching projects found.");
+            } else {
+                print_records(self.format, &PROJECT_RECORD_KEYS, &records);
+            }
         } else if self.print_path {
             let projects = get_projects(config)?;
-            if let Some(proj) = projects.print_project_path(&self.proj_name) {
+            if let Some(proj) = projects.print_project_path(&self.proj_name)? {
                 println!("{}", proj.trim());
             } else {
                 eprintln!("No matching projects found.");
             }
-        } else if self.print_uri {
+        } else if self.all_remotes {
+            let git_hosts = config.git_hosts.clone();
             let projects = get_projects(config)?;
-            if let Some(proj) = projects.matching_project(&self.proj_name) {
-                let mut git = Command::new("git");
-                let output = git
-                    .arg("-C")
-                    .arg(proj)
-                    .arg("config")
-                    .arg("--get")
-                    .arg("remote.origin.url")
-                    .stdout(Stdio::piped())
-                    .output()
-                    .expect("git should be installed");
-
-                let formatted_uri = String::from_utf8_lossy(&output.stdout)
-                    .replace(':', "/")
-                    .replace("git@", "https://")
-                    .replace(".git", "");
-                print!("{}", formatted_uri);
+            if let Some(proj) = projects.matching_project(&self.proj_name)? {
+                let names = remote_names(proj);
+                if names.is_empty() {
+                    return Err(Error::Any("Project has no git remotes".to_owned()));
+                }
+                let width = names
+                    .iter()
+                    .map(|name| name.len())
+                    .max()
+                    .unwrap_or_default();
+                for name in &names {
+                    // a remote without a web url doesn't hide the others
+                    match web_remote(proj, name, &git_hosts) {
+                        Ok((remote, _)) => println!("{name:<width$} : {}", remote.web_url()),
+                        Err(err) => println!("{name:<width$} : {err}"),
+                    }
+                }
+            } else {
+                println!("No matching projects found. Couldn't switch to project dir'");
+            }
+        } else if self.print_uri || self.web.is_some() {
+            let git_hosts = config.git_hosts.clone();
+            let projects = get_projects(config)?;
+            if let Some(proj) = projects.matching_project(&self.proj_name)? {
+                let name = self.remote.as_deref().unwrap_or("origin");
+                let (remote, forge) = web_remote(proj, name, &git_hosts)?;
+                let url = match self.web.unwrap_or(WebLink::Repo) {
+                    // repo page is at the same url on every host
+                    WebLink::Repo => remote.web_url(),
+                    link => {
+                        let forge = forge.ok_or(Error::Any(format!(
+                            "Unknown git host '{}', map it to a forge with `git_host` in config",
+                            remote.host
+                        )))?;
+                        let branch = match link.needs_branch() {
+                            true => current_branch(proj)?,
+                            false => String::new(),
+                        };
+                        remote.link_url(forge, link, &branch)
+                    }
+                };
+                if self.launch {
+                    launch(&url, &TermEnv::current())?;
+                } else {
+                    println!("{url}");
+                }
             } else {
                 println!("No matching projects found. Couldn't switch to project dir'");
             }
         } else {
             let projects = get_projects(config)?;
-            projects.open_project_in_nvim(&self.proj_name)?;
+            projects.open_project_in_editor(&self.proj_name)?;
         }
         Ok(())
     }
 }
+
+fn git_output(proj: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(proj)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("git should be installed");
+    String::from_utf8_lossy(&output.stdout).trim().to_owned()
+}
+
+fn remote_names(proj: &Path) -> Vec<String> {
+    git_output(proj, &["remote"])
+        .lines()
+        .map(|name| name.to_owned())
+        .collect()
+}
+
+/// Remote `name` of the project & forge of its host, if known
+fn web_remote(
+    proj: &Path,
+    name: &str,
+    git_hosts: &[(String, Forge)],
+) -> Result<(RemoteUrl, Option<Forge>)> {
+    let url = git_output(proj, &["config", "--get", &format!("remote.{name}.url")]);
+    if url.is_empty() {
+        let names = remote_names(proj);
+        if names.is_empty() {
+            return Err(Error::Any("Project has no git remotes".to_owned()));
+        }
+        return Err(Error::Any(format!(
+            "Project has no `{name}` remote, try --remote|-r with one of: {}",
+            names.join(", ")
+        )));
+    }
+    let Some(remote) = RemoteUrl::parse(&url) else {
+        return Err(Error::Any(format!("Unable to parse remote url '{url}'")));
+    };
+    let forge = remote.forge(git_hosts);
+    Ok((remote, forge))
+}
+
+pub fn current_branch(proj: &Path) -> Result<String> {
+    let branch = git_output(proj, &["symbolic-ref", "--quiet", "--short", "HEAD"]);
+    if branch.is_empty() {
+        return Err(Error::Any(
+            "Project is not on a branch, HEAD is detached".to_owned(),
+        ));
+    }
+    Ok(branch)
+}
+
+pub fn launch(url: &str, env: &TermEnv) -> Result<()> {
+    if !env.executable_exists("xdg-open") {
+        return Err(Error::Any(
+            "xdg-open not found in PATH, try again without --launch".to_owned(),
+        ));
+    }
+    let status = env
+        .command("xdg-open")
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(Error::Any(format!("xdg-open failed to open '{url}'")));
+    }
+    Ok(())
+}
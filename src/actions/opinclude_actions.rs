@@ -1,7 +1,8 @@
-use std::fs::File;
+use std::fs::{read_to_string, File};
 use std::io::Write;
 
-use crate::utils::{get_config_path, ActionTrait, HelpTrait};
+use crate::config::{append_include, ConfigFile};
+use crate::utils::{ActionTrait, HelpTrait};
 use crate::Config;
 
 #[derive(PartialEq, Debug)]
@@ -12,7 +13,7 @@ pub struct IncludeAction {
 
 impl HelpTrait for IncludeAction {
     fn print_help(&self) {
-        println!("op --add|-a <path>            : Adds a path to `.opinclude`");
+        println!("op --add|-a <path>            : Adds a path to includes in config");
     }
 }
 
@@ -21,12 +22,20 @@ impl ActionTrait for IncludeAction {
         if self.help {
             self.print_help();
         } else {
-            let config_path = get_config_path()?;
-            let mut config_file = File::options()
-                .create(true)
-                .append(true)
-                .open(config_path)?;
-            writeln!(&mut config_file, "include={}", self.path)?;
+            match ConfigFile::locate()? {
+                ConfigFile::Toml(config_path) => {
+                    let content = read_to_string(&config_path)?;
+                    let output = append_include(&content, &config_path, &self.path)?;
+                    std::fs::write(config_path, output)?;
+                }
+                ConfigFile::Legacy(config_path) => {
+                    let mut config_file = File::options()
+                        .create(true)
+                        .append(true)
+                        .open(config_path)?;
+                    writeln!(&mut config_file, "include={}", self.path)?;
+                }
+            }
         }
         Ok(())
     }
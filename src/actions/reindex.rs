@@ -0,0 +1,31 @@
+use crate::error::{Error, Result};
+use crate::utils::index::ProjectIndex;
+use crate::utils::projects::Projects;
+use crate::utils::{ActionTrait, HelpTrait};
+use crate::Config;
+
+#[derive(Debug, PartialEq, Default)]
+pub struct ReindexAction {
+    pub help: bool,
+}
+impl HelpTrait for ReindexAction {
+    fn print_help(&self) {
+        println!("op --reindex|-r               : Rescans all project roots and rebuilds the cached project index");
+    }
+}
+impl ActionTrait for ReindexAction {
+    fn execute(&self, config: Config) -> Result<()> {
+        if self.help {
+            self.print_help();
+        } else {
+            if !config.projects_root.try_exists()? {
+                return Err(Error::NoProjectsFound);
+            }
+            let mut index = ProjectIndex::default();
+            let count = Projects::get_root_items(&config, &mut index, true)?.len();
+            index.save()?;
+            println!("Indexed {count} project(s)");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use crate::error::Result;
+use crate::utils::select_ui::render_loop;
+use crate::utils::{ActionTrait, HelpTrait};
+use crate::Config;
+
+#[derive(Debug, PartialEq, Default)]
+pub struct SelectPrintAction {
+    pub help: bool,
+}
+impl HelpTrait for SelectPrintAction {
+    fn print_help(&self) {
+        println!("op --print|-p                 : Prints paths of projects chosen in select UI to stdout, one per line");
+        println!("Select UI is drawn on stderr, so it works in `cd \"$(op --print)\"`");
+    }
+}
+impl ActionTrait for SelectPrintAction {
+    fn execute(&self, config: Config) -> Result<()> {
+        if self.help {
+            self.print_help();
+            return Ok(());
+        }
+        render_loop(config, true)
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::{Config, ConfigEntry, Setting};
+use crate::error::Result;
+use crate::utils::index::ProjectIndex;
+use crate::utils::projects::{unique_ids, Projects};
+use crate::utils::terminal::TerminalBackend;
+use crate::utils::validate_dir_name;
+
+/// A problem found in config along with the line it was found on
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct Checker {
+    diagnostics: Vec<Diagnostic>,
+    // first line a single valued setting or a named item was defined on
+    seen: HashMap<String, usize>,
+}
+
+impl Checker {
+    fn report(&mut self, line: usize, message: String) {
+        self.diagnostics.push(Diagnostic { line, message });
+    }
+
+    fn first_seen(&mut self, key: String, line: usize) -> Option<usize> {
+        match self.seen.get(&key) {
+            Some(first) => Some(*first),
+            None => {
+                self.seen.insert(key, line);
+                None
+            }
+        }
+    }
+
+    fn single(&mut self, line: usize, name: &str) {
+        if let Some(first) = self.first_seen(name.to_owned(), line) {
+            self.report(
+                line,
+                format!("{name} is already set on line {first}, this value overrides it"),
+            );
+        }
+    }
+
+    fn existing_path(&mut self, line: usize, name: &str, path: &str) {
+        if !PathBuf::from(path).exists() {
+            self.report(line, format!("{name} '{path}' doesn't exist"));
+        }
+    }
+
+    fn unique_path(&mut self, line: usize, name: &str, path: &str) {
+        if let Some(first) = self.first_seen(format!("path:{path}"), line) {
+            self.report(
+                line,
+                format!("{name} '{path}' is a duplicate of line {first}"),
+            );
+        }
+    }
+}
+
+fn project_paths(config: &Config, root_items: &[PathBuf]) -> Vec<PathBuf> {
+    let includes = config.include.iter().map(PathBuf::from);
+    let mut paths = root_items.to_vec();
+    for path in includes {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Number of projects `name` refers to, same as how projects are opened
+fn matching_projects(paths: &[PathBuf], ids: &HashMap<PathBuf, String>, name: &str) -> usize {
+    if ids.values().any(|id| id == name) {
+        return 1;
+    }
+    paths.iter().filter(|path| path.ends_with(name)).count()
+}
+
+/// Validates config entries. Checks for unknown keys, invalid values, missing
+/// paths, compound projects referencing unknown projects and duplicates.
+/// Projects under roots are looked up through `index`
+pub fn check(entries: &[ConfigEntry], index: &mut ProjectIndex) -> Result<Vec<Diagnostic>> {
+    let mut config = Config::from_entries(entries)?;
+
+    // projects under roots are needed to validate includes & compound projects.
+    // Missing roots are reported below so they are skipped here
+    let root_items = match config.projects_root.exists() {
+        true => {
+            config.extra_roots.retain(|root| root.exists());
+            Projects::get_root_items(&config, index, false)?
+        }
+        false => Vec::new(),
+    };
+    let paths = project_paths(&config, &root_items);
+    let ids = unique_ids(&paths);
+    let names = paths
+        .iter()
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().to_string()))
+        .collect::<Vec<_>>();
+
+    let mut checker = Checker::default();
+    for entry in entries {
+        let line = entry.line;
+        match &entry.setting {
+            Setting::ProjectsRoot(path) => {
+                checker.single(line, "projects root");
+                checker.existing_path(line, "projects root", path);
+                checker.unique_path(line, "projects root", path);
+            }
+            Setting::ExtraRoot(path) => {
+                checker.existing_path(line, "extra projects root", path);
+                checker.unique_path(line, "extra projects root", path);
+            }
+            Setting::Include(path) => {
+                checker.existing_path(line, "include", path);
+                checker.unique_path(line, "include", path);
+                if root_items.contains(&PathBuf::from(path)) {
+                    checker.report(
+                        line,
+                        format!("include '{path}' is already tracked under a projects root"),
+                    );
+                }
+            }
+            Setting::IgnoreDir(_) => checker.single(line, "ignore dir"),
+            Setting::Editor(_) => checker.single(line, "editor"),
+            Setting::Discovery(value) => {
+                checker.single(line, "discovery");
+                if Config::parse_discovery_config(value).is_none() {
+                    checker.report(
+                        line,
+                        format!("discovery '{value}' should be either 'layout' or 'markers'"),
+                    );
+                }
+            }
+            Setting::MaxDepth(value) => {
+                checker.single(line, "max depth");
+                if value.parse::<usize>().is_err() {
+                    checker.report(
+                        line,
+                        format!("max depth '{value}' should be a positive number"),
+                    );
+                }
+            }
+            Setting::Terminal(value) => {
+                checker.single(line, "terminal");
+                if TerminalBackend::from_name(value).is_none() {
+                    checker.report(
+                        line,
+                        format!(
+                            "terminal '{value}' should be one of wezterm, tmux, zellij or kitty"
+                        ),
+                    );
+                }
+            }
+            Setting::Sessionizer(value) => {
+                checker.single(line, "sessionizer");
+                if Config::parse_bool_config(value).is_none() {
+                    checker.report(
+                        line,
+                        format!("sessionizer '{value}' should be either 'true' or 'false'"),
+                    );
+                }
+            }
+            Setting::Markers(markers) => {
+                if markers.is_empty() {
+                    checker.report(line, "markers shouldn't be empty".to_owned());
+                }
+            }
+            Setting::Languages(languages) => {
+                if languages.is_empty() {
+                    checker.report(line, "languages shouldn't be empty".to_owned());
+                }
+                for language in languages {
+                    if validate_dir_name("language", language).is_err() {
+                        checker.report(
+                            line,
+                            format!("language '{language}' should be a single directory name"),
+                        );
+                    }
+                }
+            }
+            Setting::GitHost(host, forge) => {
+                if Config::parse_git_host_config(host, forge).is_none() {
+                    checker.report(
+                        line,
+                        format!("git host '{host}' should be mapped to one of github, gitlab, bitbucket or gitea, not '{forge}'"),
+                    );
+                }
+                checker.single(line, &format!("git host '{}'", host.trim().to_lowercase()));
+            }
+            Setting::ProjectEditor(name, editor) => {
+                if name.is_empty() || editor.trim().is_empty() {
+                    checker.report(
+                        line,
+                        "project editor needs both a project name and a command".to_owned(),
+                    );
+                } else if let Some(first) = checker.first_seen(format!("editor:{name}"), line) {
+                    checker.report(
+                        line,
+                        format!("editor for '{name}' is already set on line {first}, this one is ignored"),
+                    );
+                }
+                if !name.is_empty() && !names.contains(name) {
+                    checker.report(line, format!("editor set for unknown project '{name}'"));
+                }
+            }
+            Setting::CompoundProject(items) => {
+                let Some((name, projects)) = items.split_first() else {
+                    continue;
+                };
+                if Config::parse_compund_projects_config(items).is_none() {
+                    checker.report(
+                        line,
+                        format!("compound project '{name}' should have a name and at least one project, it is ignored"),
+                    );
+                }
+                if let Some(first) = checker.first_seen(format!("compound:{name}"), line) {
+                    checker.report(
+                        line,
+                        format!("compound project '{name}' is already defined on line {first}, this one is ignored"),
+                    );
+                }
+                for project in projects {
+                    match matching_projects(&paths, &ids, project) {
+                        0 => checker.report(
+                            line,
+                            format!(
+                                "compound project '{name}' references unknown project '{project}'"
+                            ),
+                        ),
+                        1 => {}
+                        _ => checker.report(
+                            line,
+                            format!("compound project '{name}' references ambiguous project '{project}', use its id like 'parent_dir/{project}'"),
+                        ),
+                    }
+                }
+            }
+            Setting::Unknown(key, _) => checker.report(line, format!("unknown key '{key}'")),
+            Setting::Invalid(value, reason) => {
+                checker.report(line, format!("invalid '{value}', {reason}"))
+            }
+            Setting::Comment(_) => {}
+        }
+    }
+    Ok(checker.diagnostics)
+}
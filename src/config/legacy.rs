@@ -0,0 +1,81 @@
+use super::{ConfigEntry, Setting};
+use crate::utils::constants::{
+    CONFIGFILE_COMPOUND_PROJECTS, CONFIGFILE_DISCOVERY, CONFIGFILE_EDITOR,
+    CONFIGFILE_EXTRA_PROJECTS_ROOT, CONFIGFILE_GIT_HOST, CONFIGFILE_IGNORE_DIR, CONFIGFILE_INCLUDE,
+    CONFIGFILE_LANGUAGES, CONFIGFILE_MARKERS, CONFIGFILE_MAX_DEPTH, CONFIGFILE_PROJECTS_ROOT,
+    CONFIGFILE_PROJECT_EDITOR, CONFIGFILE_SESSIONIZER, CONFIGFILE_TERMINAL,
+};
+
+fn parse_line(line: &str) -> Option<Setting> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    if let Some(comment) = line.strip_prefix('#') {
+        return Some(Setting::Comment(comment.to_owned()));
+    }
+    let Some((key, value)) = line.split_once('=') else {
+        return Some(Setting::Invalid(
+            line.to_owned(),
+            "expected a `key=value` pair".to_owned(),
+        ));
+    };
+
+    let setting = match key {
+        CONFIGFILE_PROJECTS_ROOT => Setting::ProjectsRoot(value.to_owned()),
+        CONFIGFILE_IGNORE_DIR => Setting::IgnoreDir(value.to_owned()),
+        CONFIGFILE_INCLUDE => Setting::Include(value.to_owned()),
+        CONFIGFILE_COMPOUND_PROJECTS => {
+            Setting::CompoundProject(value.split(',').map(|s| s.to_owned()).collect())
+        }
+        CONFIGFILE_EXTRA_PROJECTS_ROOT => Setting::ExtraRoot(value.to_owned()),
+        CONFIGFILE_EDITOR => Setting::Editor(value.to_owned()),
+        CONFIGFILE_PROJECT_EDITOR => match value.split_once(',') {
+            Some((project_name, editor)) => {
+                Setting::ProjectEditor(project_name.to_owned(), editor.to_owned())
+            }
+            None => Setting::Invalid(
+                line.to_owned(),
+                "expected `project_name,editor command`".to_owned(),
+            ),
+        },
+        CONFIGFILE_DISCOVERY => Setting::Discovery(value.to_owned()),
+        CONFIGFILE_MAX_DEPTH => Setting::MaxDepth(value.to_owned()),
+        CONFIGFILE_MARKERS => Setting::Markers(
+            value
+                .split(',')
+                .filter(|m| !m.is_empty())
+                .map(|m| m.to_owned())
+                .collect(),
+        ),
+        CONFIGFILE_LANGUAGES => Setting::Languages(
+            value
+                .split(',')
+                .filter(|l| !l.is_empty())
+                .map(|l| l.to_owned())
+                .collect(),
+        ),
+        CONFIGFILE_TERMINAL => Setting::Terminal(value.to_owned()),
+        CONFIGFILE_SESSIONIZER => Setting::Sessionizer(value.to_owned()),
+        CONFIGFILE_GIT_HOST => match value.split_once(',') {
+            Some((host, forge)) => Setting::GitHost(host.to_owned(), forge.to_owned()),
+            None => Setting::Invalid(line.to_owned(), "expected `host,forge`".to_owned()),
+        },
+        _ => Setting::Unknown(key.to_owned(), value.to_owned()),
+    };
+    Some(setting)
+}
+
+/// Parses `.opconfig`, a plain `key=value` per line file. Lines starting with
+/// `#` are comments
+pub fn parse(content: &str) -> Vec<ConfigEntry> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            parse_line(line).map(|setting| ConfigEntry {
+                line: idx + 1,
+                setting,
+            })
+        })
+        .collect()
+}
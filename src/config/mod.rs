@@ -0,0 +1,277 @@
+mod check;
+mod legacy;
+mod toml_file;
+
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use crate::error::Result;
+use crate::utils::constants::{
+    DEFAULT_IGNORE_DIR, DEFAULT_LANGUAGES, DEFAULT_MARKERS, DEFAULT_MAX_DEPTH,
+    DEFAULT_PROJECTS_ROOT, OP_TOML_CONFIG,
+};
+use crate::utils::index::DiscoveryMode;
+use crate::utils::remote_url::Forge;
+use crate::utils::terminal::TerminalBackend;
+use crate::utils::{get_config_dir, get_config_path, get_profile_path};
+
+pub use check::check;
+pub use toml_file::append_include;
+
+#[derive(Debug, Default)]
+pub struct Config {
+    pub projects_root: PathBuf,
+    pub extra_roots: Vec<PathBuf>,
+    pub ignore_dir: String,
+    pub include: Vec<String>,
+    pub compound_projects: Vec<CompoundProject>,
+    pub editor: Option<String>,
+    pub project_editors: Vec<[String; 2]>,
+    pub discovery: DiscoveryMode,
+    pub max_depth: usize,
+    pub markers: Vec<String>,
+    pub terminal: TerminalBackend,
+    // open projects in a tmux session of their own instead of current shell
+    pub sessionizer: bool,
+    // self hosted git hosts & their forge, for web urls of remotes
+    pub git_hosts: Vec<(String, Forge)>,
+    // `language_dir`s created by `--create`
+    pub languages: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompoundProject {
+    pub name: String,
+    // names of projects in the order they are opened
+    pub projects: Vec<String>,
+}
+
+/// A single setting read from either of the config files
+#[derive(Debug, Clone, PartialEq)]
+pub enum Setting {
+    ProjectsRoot(String),
+    ExtraRoot(String),
+    IgnoreDir(String),
+    Include(String),
+    // compound project name followed by names of its projects
+    CompoundProject(Vec<String>),
+    Editor(String),
+    // project name & its editor command
+    ProjectEditor(String, String),
+    Discovery(String),
+    MaxDepth(String),
+    Markers(Vec<String>),
+    Terminal(String),
+    Sessionizer(String),
+    // host of git remotes & its forge name
+    GitHost(String, String),
+    Languages(Vec<String>),
+
+    // below are never applied to `Config` and only kept around so nothing is
+    // lost while migrating
+    Comment(String),
+    Unknown(String, String),
+    Invalid(String, String),
+}
+
+/// `Setting` along with the line number it was found on
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigEntry {
+    pub line: usize,
+    pub setting: Setting,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigFile {
+    // `op.toml` in config dir
+    Toml(PathBuf),
+    // `.opconfig` in home dir
+    Legacy(PathBuf),
+}
+
+impl ConfigFile {
+    /// `op.toml` takes precedence, `.opconfig` is only read when it doesn't exist
+    pub fn locate() -> Result<Self> {
+        let toml_path = Self::toml_path()?;
+        if toml_path.exists() {
+            return Ok(Self::Toml(toml_path));
+        }
+        Ok(Self::Legacy(get_config_path()?))
+    }
+
+    pub fn toml_path() -> Result<PathBuf> {
+        Ok(get_config_dir()?.join(OP_TOML_CONFIG))
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            Self::Toml(path) | Self::Legacy(path) => path,
+        }
+    }
+
+    pub fn read_entries(&self) -> Result<Vec<ConfigEntry>> {
+        if !self.path().exists() {
+            return Ok(Vec::new());
+        }
+        let content = read_to_string(self.path())?;
+        match self {
+            Self::Toml(path) => toml_file::parse(&content, path),
+            Self::Legacy(_) => Ok(legacy::parse(&content)),
+        }
+    }
+}
+
+/// Converts `.opconfig` content to `op.toml` content
+pub fn migrate_legacy(content: &str) -> String {
+    toml_file::write(&legacy::parse(content))
+}
+
+impl Config {
+    fn parse_compund_projects_config(value: &[String]) -> Option<CompoundProject> {
+        let (name, projects) = value.split_first()?;
+        if name.is_empty() || projects.is_empty() || projects.iter().any(|p| p.is_empty()) {
+            return None;
+        }
+        Some(CompoundProject {
+            name: name.to_owned(),
+            projects: projects.to_vec(),
+        })
+    }
+
+    fn parse_project_editor_config(project_name: &str, editor: &str) -> Option<[String; 2]> {
+        if project_name.is_empty() || editor.trim().is_empty() {
+            return None;
+        }
+        Some([project_name.to_owned(), editor.to_owned()])
+    }
+
+    fn parse_discovery_config(value: &str) -> Option<DiscoveryMode> {
+        match value {
+            "layout" => Some(DiscoveryMode::Layout),
+            "markers" => Some(DiscoveryMode::Markers),
+            _ => None,
+        }
+    }
+
+    pub fn discovery_markers(&self) -> Vec<String> {
+        if self.markers.is_empty() {
+            return DEFAULT_MARKERS.iter().map(|m| m.to_string()).collect();
+        }
+        self.markers.clone()
+    }
+
+    pub fn layout_languages(&self) -> Vec<String> {
+        if self.languages.is_empty() {
+            return DEFAULT_LANGUAGES.iter().map(|l| l.to_string()).collect();
+        }
+        self.languages.clone()
+    }
+
+    fn parse_bool_config(value: &str) -> Option<bool> {
+        match value {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn parse_git_host_config(host: &str, forge: &str) -> Option<(String, Forge)> {
+        if host.trim().is_empty() {
+            return None;
+        }
+        Some((host.trim().to_lowercase(), Forge::from_name(forge)?))
+    }
+
+    fn apply(&mut self, setting: &Setting) {
+        match setting {
+            Setting::ProjectsRoot(value) => {
+                self.projects_root = PathBuf::from(value);
+            }
+            Setting::IgnoreDir(value) => {
+                self.ignore_dir = value.to_owned();
+            }
+            Setting::Include(value) => {
+                self.include.push(value.to_owned());
+            }
+            Setting::CompoundProject(value) => {
+                if let Some(x) = Self::parse_compund_projects_config(value) {
+                    self.compound_projects.push(x);
+                }
+            }
+            Setting::ExtraRoot(value) => {
+                self.extra_roots.push(PathBuf::from(value));
+            }
+            Setting::Editor(value) => {
+                self.editor = Some(value.to_owned());
+            }
+            Setting::ProjectEditor(project_name, editor) => {
+                if let Some(x) = Self::parse_project_editor_config(project_name, editor) {
+                    self.project_editors.push(x);
+                }
+            }
+            Setting::Discovery(value) => {
+                if let Some(x) = Self::parse_discovery_config(value) {
+                    self.discovery = x;
+                }
+            }
+            Setting::MaxDepth(value) => {
+                if let Ok(x) = value.parse() {
+                    self.max_depth = x;
+                }
+            }
+            Setting::Markers(value) => {
+                self.markers.extend(value.iter().cloned());
+            }
+            Setting::Terminal(value) => {
+                if let Some(x) = TerminalBackend::from_name(value) {
+                    self.terminal = x;
+                }
+            }
+            Setting::Sessionizer(value) => {
+                if let Some(x) = Self::parse_bool_config(value) {
+                    self.sessionizer = x;
+                }
+            }
+            Setting::GitHost(host, forge) => {
+                if let Some(x) = Self::parse_git_host_config(host, forge) {
+                    // later ones override earlier ones of the same host
+                    self.git_hosts.retain(|(host, _)| *host != x.0);
+                    self.git_hosts.push(x);
+                }
+            }
+            Setting::Languages(value) => {
+                self.languages.extend(value.iter().cloned());
+            }
+            Setting::Comment(_) | Setting::Unknown(..) | Setting::Invalid(..) => {}
+        }
+    }
+
+    pub fn from_entries(entries: &[ConfigEntry]) -> Result<Self> {
+        let home_dir = PathBuf::from(&get_profile_path()?);
+        let mut config = Config {
+            projects_root: home_dir.join(DEFAULT_PROJECTS_ROOT),
+            extra_roots: Vec::new(),
+            ignore_dir: DEFAULT_IGNORE_DIR.to_owned(),
+            include: Vec::new(),
+            compound_projects: Vec::new(),
+            editor: None,
+            project_editors: Vec::new(),
+            discovery: DiscoveryMode::Layout,
+            max_depth: DEFAULT_MAX_DEPTH,
+            markers: Vec::new(),
+            terminal: TerminalBackend::Wezterm,
+            sessionizer: false,
+            git_hosts: Vec::new(),
+            languages: Vec::new(),
+        };
+        for entry in entries {
+            config.apply(&entry.setting);
+        }
+        Ok(config)
+    }
+
+    pub fn new() -> Result<Self> {
+        let entries = ConfigFile::locate()?.read_entries()?;
+        Self::from_entries(&entries)
+    }
+}
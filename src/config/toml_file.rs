@@ -0,0 +1,595 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+use toml::de::{DeTable, DeValue};
+use toml::Spanned;
+
+use super::{ConfigEntry, Setting};
+use crate::error::{Error, Result};
+
+////////////////////////////////////////////////////////////////////////////////
+// `op.toml` reader & writer
+////////////////////////////////////////////////////////////////////////////////
+//
+// [roots]
+// projects_root = "/path/to/Projects"
+// extra = ["/another/path"]
+//
+// [ignore]
+// dir = "ignore"
+//
+// [include]
+// paths = ["/path/to/project_dir"]
+//
+// [editor]
+// command = "hx {path}"
+// projects = { project_name = "code --wait {path}" }
+//
+// [discovery]
+// mode = "markers"
+// max_depth = 3
+// markers = [".git", "Cargo.toml"]
+//
+// [layout]
+// languages = ["rust", "go"]
+//
+// [terminal]
+// backend = "tmux"
+// sessionizer = true
+//
+// [git_hosts]
+// "git.example.com" = "gitlab"
+//
+// [[compound_projects]]
+// name = "compound_project_name"
+// projects = ["project_1_name", "project_2_name"]
+
+type Value<'i> = Spanned<DeValue<'i>>;
+
+struct Parser<'a> {
+    content: &'a str,
+    entries: Vec<ConfigEntry>,
+}
+
+fn line_of(content: &str, span: Range<usize>) -> usize {
+    content[..span.start.min(content.len())]
+        .matches('\n')
+        .count()
+        + 1
+}
+
+impl Parser<'_> {
+    fn push(&mut self, span: Range<usize>, setting: Setting) {
+        self.entries.push(ConfigEntry {
+            line: line_of(self.content, span),
+            setting,
+        });
+    }
+
+    fn raw(&self, value: &Value) -> String {
+        self.content[value.span()].to_owned()
+    }
+
+    fn invalid(&mut self, key: &str, value: &Value, reason: &str) {
+        let setting = Setting::Invalid(format!("{key} = {}", self.raw(value)), reason.to_owned());
+        self.push(value.span(), setting);
+    }
+
+    fn string(&mut self, key: &str, value: &Value, setting: impl Fn(String) -> Setting) {
+        match value.get_ref().as_str() {
+            Some(s) => self.push(value.span(), setting(s.to_owned())),
+            None => self.invalid(key, value, "expected a string"),
+        }
+    }
+
+    fn strings(&mut self, key: &str, value: &Value) -> Option<Vec<String>> {
+        let Some(array) = value.get_ref().as_array() else {
+            self.invalid(key, value, "expected an array of strings");
+            return None;
+        };
+        let mut items = Vec::new();
+        for item in array.iter() {
+            match item.get_ref().as_str() {
+                Some(s) => items.push(s.to_owned()),
+                None => self.invalid(key, item, "expected a string"),
+            }
+        }
+        Some(items)
+    }
+
+    fn each_string(&mut self, key: &str, value: &Value, setting: impl Fn(String) -> Setting) {
+        let Some(array) = value.get_ref().as_array() else {
+            self.invalid(key, value, "expected an array of strings");
+            return;
+        };
+        for item in array.iter() {
+            self.string(key, item, &setting);
+        }
+    }
+
+    fn table(
+        &mut self,
+        section: &str,
+        value: &Value,
+        mut handle: impl FnMut(&mut Self, &str, &Value) -> bool,
+    ) {
+        let Some(table) = value.get_ref().as_table() else {
+            self.invalid(section, value, "expected a table");
+            return;
+        };
+        for (key, value) in table.iter() {
+            let key = key.get_ref().as_ref();
+            if !handle(self, key, value) {
+                let setting = Setting::Unknown(format!("{section}.{key}"), self.raw(value));
+                self.push(value.span(), setting);
+            }
+        }
+    }
+
+    fn compound_project(&mut self, value: &Value) {
+        let Some(table) = value.get_ref().as_table() else {
+            self.invalid("compound_projects", value, "expected a table");
+            return;
+        };
+        let mut name = None;
+        let mut projects = Vec::new();
+        for (key, item) in table.iter() {
+            match key.get_ref().as_ref() {
+                "name" => match item.get_ref().as_str() {
+                    Some(s) => name = Some(s.to_owned()),
+                    None => self.invalid("compound_projects.name", item, "expected a string"),
+                },
+                "projects" => {
+                    projects = self
+                        .strings("compound_projects.projects", item)
+                        .unwrap_or_default();
+                }
+                key => {
+                    let setting =
+                        Setting::Unknown(format!("compound_projects.{key}"), self.raw(item));
+                    self.push(item.span(), setting);
+                }
+            }
+        }
+        match name {
+            Some(name) => {
+                let mut compound = vec![name];
+                compound.extend(projects);
+                self.push(value.span(), Setting::CompoundProject(compound));
+            }
+            None => self.invalid("compound_projects", value, "missing `name`"),
+        }
+    }
+
+    fn root_key(&mut self, key: &str, value: &Value) {
+        match key {
+            "roots" => self.table(key, value, |p, key, value| {
+                match key {
+                    "projects_root" => p.string(key, value, Setting::ProjectsRoot),
+                    "extra" => p.each_string(key, value, Setting::ExtraRoot),
+                    _ => return false,
+                }
+                true
+            }),
+            "ignore" => self.table(key, value, |p, key, value| {
+                match key {
+                    "dir" => p.string(key, value, Setting::IgnoreDir),
+                    _ => return false,
+                }
+                true
+            }),
+            "include" => self.table(key, value, |p, key, value| {
+                match key {
+                    "paths" => p.each_string(key, value, Setting::Include),
+                    _ => return false,
+                }
+                true
+            }),
+            "editor" => self.table(key, value, |p, key, value| {
+                match key {
+                    "command" => p.string(key, value, Setting::Editor),
+                    "projects" => p.table("editor.projects", value, |p, name, value| {
+                        let name = name.to_owned();
+                        p.string(name.as_str(), value, |editor| {
+                            Setting::ProjectEditor(name.to_owned(), editor)
+                        });
+                        true
+                    }),
+                    _ => return false,
+                }
+                true
+            }),
+            "discovery" => self.table(key, value, |p, key, value| {
+                match key {
+                    "mode" => p.string(key, value, Setting::Discovery),
+                    "max_depth" => match value.get_ref() {
+                        DeValue::Integer(x) => {
+                            p.push(value.span(), Setting::MaxDepth(x.as_str().to_owned()))
+                        }
+                        DeValue::String(x) => {
+                            p.push(value.span(), Setting::MaxDepth(x.to_string()))
+                        }
+                        _ => p.invalid(key, value, "expected an integer"),
+                    },
+                    "markers" => {
+                        if let Some(markers) = p.strings(key, value) {
+                            p.push(value.span(), Setting::Markers(markers));
+                        }
+                    }
+                    _ => return false,
+                }
+                true
+            }),
+            "layout" => self.table(key, value, |p, key, value| {
+                match key {
+                    "languages" => {
+                        if let Some(languages) = p.strings(key, value) {
+                            p.push(value.span(), Setting::Languages(languages));
+                        }
+                    }
+                    _ => return false,
+                }
+                true
+            }),
+            "terminal" => self.table(key, value, |p, key, value| {
+                match key {
+                    "backend" => p.string(key, value, Setting::Terminal),
+                    "sessionizer" => match value.get_ref() {
+                        DeValue::Boolean(x) => {
+                            p.push(value.span(), Setting::Sessionizer(x.to_string()))
+                        }
+                        _ => p.invalid(key, value, "expected a boolean"),
+                    },
+                    _ => return false,
+                }
+                true
+            }),
+            "git_hosts" => self.table(key, value, |p, host, value| {
+                let host = host.to_owned();
+                p.string(host.as_str(), value, |forge| {
+                    Setting::GitHost(host.to_owned(), forge)
+                });
+                true
+            }),
+            "compound_projects" => match value.get_ref().as_array() {
+                Some(array) => {
+                    for item in array.iter() {
+                        self.compound_project(item);
+                    }
+                }
+                None => self.invalid(key, value, "expected an array of tables"),
+            },
+            _ => {
+                let setting = Setting::Unknown(key.to_owned(), self.raw(value));
+                self.push(value.span(), setting);
+            }
+        }
+    }
+}
+
+fn parse_table<'i>(content: &'i str, path: &Path) -> Result<Spanned<DeTable<'i>>> {
+    DeTable::parse(content).map_err(|err| Error::Any(format!("{}: {err}", path.display())))
+}
+
+/// Parses `op.toml` content into config entries
+pub fn parse(content: &str, path: &Path) -> Result<Vec<ConfigEntry>> {
+    let table = parse_table(content, path)?;
+    let mut parser = Parser {
+        content,
+        entries: Vec::new(),
+    };
+    for (key, value) in table.get_ref().iter() {
+        parser.root_key(key.get_ref().as_ref(), value);
+    }
+    parser.entries.sort_by_key(|entry| entry.line);
+    Ok(parser.entries)
+}
+
+fn quote(value: &str) -> String {
+    // literal strings keep windows paths readable, basic strings otherwise
+    if !value.contains(['\'', '\n', '\r']) {
+        return format!("'{value}'");
+    }
+    let mut output = String::from('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => output.push_str(&format!("\\u{:04X}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+fn quote_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-');
+    match bare {
+        true => key.to_owned(),
+        false => quote(key),
+    }
+}
+
+fn quote_list(values: &[String]) -> String {
+    let values = values.iter().map(|v| quote(v)).collect::<Vec<_>>();
+    format!("[{}]", values.join(", "))
+}
+
+/// `line` preceded by the comments which were above its setting
+fn noted(notes: &mut HashMap<String, Vec<String>>, id: &str, line: String) -> String {
+    match notes.remove(id) {
+        Some(comments) => format!("{}\n{line}", comments.join("\n")),
+        None => line,
+    }
+}
+
+/// Writes config entries as `op.toml` content. Settings which can't be
+/// represented, e.g unknown keys or overridden values, are kept as comments.
+/// Comments stay right above the key of the setting which followed them
+pub fn write(entries: &[ConfigEntry]) -> String {
+    // comments waiting for the next setting & comments by key they belong to
+    let mut pending = Vec::new();
+    let mut notes: HashMap<String, Vec<String>> = HashMap::new();
+    let mut unused = Vec::new();
+
+    let mut projects_root = None;
+    let mut extra_roots = Vec::new();
+    let mut ignore_dir = None;
+    let mut include = Vec::new();
+    let mut editor = None;
+    let mut project_editors: Vec<(String, String)> = Vec::new();
+    let mut discovery = None;
+    let mut max_depth = None;
+    let mut markers: Option<Vec<String>> = None;
+    let mut languages: Option<Vec<String>> = None;
+    let mut terminal = None;
+    let mut sessionizer = None;
+    let mut git_hosts: Vec<(String, String)> = Vec::new();
+    let mut compound_projects = Vec::new();
+
+    // later values override earlier ones for single value settings
+    let replace = |slot: &mut Option<String>, unused: &mut Vec<String>, key: &str, value: &str| {
+        if let Some(old) = slot.replace(value.to_owned()) {
+            unused.push(format!("overridden: {key}={old}"));
+        }
+    };
+    for entry in entries {
+        let id = match &entry.setting {
+            Setting::Comment(v) => {
+                pending.push(format!("#{v}"));
+                continue;
+            }
+            Setting::ProjectsRoot(_) => "projects_root".to_owned(),
+            Setting::ExtraRoot(_) => "extra".to_owned(),
+            Setting::IgnoreDir(_) => "ignore_dir".to_owned(),
+            Setting::Include(_) => "include".to_owned(),
+            Setting::Editor(_) => "editor".to_owned(),
+            Setting::ProjectEditor(name, _) => format!("editor.projects.{name}"),
+            Setting::Discovery(_) => "discovery".to_owned(),
+            Setting::MaxDepth(_) => "max_depth".to_owned(),
+            Setting::Markers(_) => "markers".to_owned(),
+            Setting::Languages(_) => "languages".to_owned(),
+            Setting::Terminal(_) => "terminal".to_owned(),
+            Setting::Sessionizer(_) => "sessionizer".to_owned(),
+            Setting::GitHost(host, _) => format!("git_hosts.{host}"),
+            Setting::CompoundProject(_) => format!("compound.{}", compound_projects.len()),
+            Setting::Unknown(..) | Setting::Invalid(..) => format!("unused.{}", unused.len()),
+        };
+        if !pending.is_empty() {
+            notes.entry(id).or_default().append(&mut pending);
+        }
+
+        let unused_ref = &mut unused;
+        match &entry.setting {
+            Setting::ProjectsRoot(v) => replace(&mut projects_root, unused_ref, "projects_root", v),
+            Setting::IgnoreDir(v) => replace(&mut ignore_dir, unused_ref, "ignore_dir", v),
+            Setting::Editor(v) => replace(&mut editor, unused_ref, "editor", v),
+            Setting::Discovery(v) => replace(&mut discovery, unused_ref, "discovery", v),
+            Setting::MaxDepth(v) => replace(&mut max_depth, unused_ref, "max_depth", v),
+            Setting::Terminal(v) => replace(&mut terminal, unused_ref, "terminal", v),
+            Setting::Sessionizer(v) => replace(&mut sessionizer, unused_ref, "sessionizer", v),
+            Setting::ExtraRoot(v) => extra_roots.push(v.to_owned()),
+            Setting::Include(v) => include.push(v.to_owned()),
+            Setting::Markers(v) => markers.get_or_insert_with(Vec::new).extend(v.clone()),
+            Setting::Languages(v) => languages.get_or_insert_with(Vec::new).extend(v.clone()),
+            Setting::CompoundProject(v) => compound_projects.push(v.clone()),
+            Setting::ProjectEditor(name, cmd) => {
+                // first override of a project is the one used
+                if project_editors.iter().any(|(n, _)| n == name) {
+                    unused.push(format!("overridden: project_editor={name},{cmd}"));
+                } else {
+                    project_editors.push((name.to_owned(), cmd.to_owned()));
+                }
+            }
+            Setting::GitHost(host, forge) => {
+                // later ones win, same as a toml table
+                if let Some(idx) = git_hosts.iter().position(|(h, _)| h == host) {
+                    let (_, old) = git_hosts.remove(idx);
+                    unused.push(format!("overridden: git_host={host},{old}"));
+                }
+                git_hosts.push((host.to_owned(), forge.to_owned()));
+            }
+            Setting::Comment(_) => {}
+            Setting::Unknown(key, value) => unused.push(format!("unknown: {key}={value}")),
+            Setting::Invalid(value, reason) => unused.push(format!("invalid: {value} ({reason})")),
+        }
+    }
+
+    let mut sections: Vec<String> = Vec::new();
+    if projects_root.is_some() || !extra_roots.is_empty() {
+        let mut section = vec!["[roots]".to_owned()];
+        if let Some(v) = projects_root {
+            let line = format!("projects_root = {}", quote(&v));
+            section.push(noted(&mut notes, "projects_root", line));
+        }
+        if !extra_roots.is_empty() {
+            let line = format!("extra = {}", quote_list(&extra_roots));
+            section.push(noted(&mut notes, "extra", line));
+        }
+        sections.push(section.join("\n"));
+    }
+    if let Some(v) = ignore_dir {
+        let line = format!("dir = {}", quote(&v));
+        sections.push(format!(
+            "[ignore]\n{}",
+            noted(&mut notes, "ignore_dir", line)
+        ));
+    }
+    if !include.is_empty() {
+        let line = format!("paths = {}", quote_list(&include));
+        sections.push(format!("[include]\n{}", noted(&mut notes, "include", line)));
+    }
+    if editor.is_some() || !project_editors.is_empty() {
+        let mut section = vec!["[editor]".to_owned()];
+        if let Some(v) = editor {
+            let line = format!("command = {}", quote(&v));
+            section.push(noted(&mut notes, "editor", line));
+        }
+        sections.push(section.join("\n"));
+        if !project_editors.is_empty() {
+            let mut section = vec!["[editor.projects]".to_owned()];
+            for (name, cmd) in project_editors {
+                let line = format!("{} = {}", quote_key(&name), quote(&cmd));
+                section.push(noted(&mut notes, &format!("editor.projects.{name}"), line));
+            }
+            sections.push(section.join("\n"));
+        }
+    }
+    if discovery.is_some() || max_depth.is_some() || markers.is_some() {
+        let mut section = vec!["[discovery]".to_owned()];
+        if let Some(v) = discovery {
+            let line = format!("mode = {}", quote(&v));
+            section.push(noted(&mut notes, "discovery", line));
+        }
+        if let Some(v) = max_depth {
+            let line = match v.parse::<usize>() {
+                Ok(depth) => format!("max_depth = {depth}"),
+                Err(_) => format!("max_depth = {}", quote(&v)),
+            };
+            section.push(noted(&mut notes, "max_depth", line));
+        }
+        if let Some(v) = markers {
+            let line = format!("markers = {}", quote_list(&v));
+            section.push(noted(&mut notes, "markers", line));
+        }
+        sections.push(section.join("\n"));
+    }
+    if let Some(v) = languages {
+        let line = format!("languages = {}", quote_list(&v));
+        sections.push(format!(
+            "[layout]\n{}",
+            noted(&mut notes, "languages", line)
+        ));
+    }
+    if terminal.is_some() || sessionizer.is_some() {
+        let mut section = vec!["[terminal]".to_owned()];
+        if let Some(v) = terminal {
+            let line = format!("backend = {}", quote(&v));
+            section.push(noted(&mut notes, "terminal", line));
+        }
+        if let Some(v) = sessionizer {
+            let line = match v.as_str() {
+                "true" | "false" => format!("sessionizer = {v}"),
+                _ => format!("sessionizer = {}", quote(&v)),
+            };
+            section.push(noted(&mut notes, "sessionizer", line));
+        }
+        sections.push(section.join("\n"));
+    }
+    if !git_hosts.is_empty() {
+        let mut section = vec!["[git_hosts]".to_owned()];
+        for (host, forge) in git_hosts {
+            let line = format!("{} = {}", quote_key(&host), quote(&forge));
+            section.push(noted(&mut notes, &format!("git_hosts.{host}"), line));
+        }
+        sections.push(section.join("\n"));
+    }
+    for (idx, compound) in compound_projects.into_iter().enumerate() {
+        let (name, projects) = compound.split_first().expect("compound project has a name");
+        let section = format!(
+            "[[compound_projects]]\nname = {}\nprojects = {}",
+            quote(name),
+            quote_list(projects)
+        );
+        sections.push(noted(&mut notes, &format!("compound.{idx}"), section));
+    }
+    if !unused.is_empty() {
+        let unused = unused
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| noted(&mut notes, &format!("unused.{idx}"), format!("# {line}")))
+            .collect::<Vec<_>>();
+        sections.push(format!(
+            "# Below lines from `.opconfig` are not used by op\n{}",
+            unused.join("\n")
+        ));
+    }
+
+    // comments at the end of `.opconfig`, notes left over are kept too so
+    // nothing is dropped
+    let mut rest = notes.into_values().flatten().collect::<Vec<_>>();
+    rest.sort();
+    rest.extend(pending);
+    if !rest.is_empty() {
+        sections.push(rest.join("\n"));
+    }
+
+    let mut output = sections.join("\n\n");
+    output.push('\n');
+    output
+}
+
+/// Adds `path` to `include.paths` of `op.toml` content while leaving
+/// everything else, including comments, untouched
+pub fn append_include(content: &str, path: &Path, include: &str) -> Result<String> {
+    let table = parse_table(content, path)?;
+    let item = quote(include);
+    let invalid = || Error::Any(format!("{}: `include` should be a table", path.display()));
+
+    let Some((key, value)) = table
+        .get_ref()
+        .iter()
+        .find(|(key, _)| key.get_ref() == "include")
+    else {
+        let separator = if content.is_empty() || content.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        return Ok(format!(
+            "{content}{separator}\n[include]\npaths = [{item}]\n"
+        ));
+    };
+    let include_table = value.get_ref().as_table().ok_or_else(invalid)?;
+
+    let mut output = content.to_owned();
+    match include_table
+        .iter()
+        .find(|(key, _)| key.get_ref() == "paths")
+    {
+        Some((_, paths)) => {
+            let array = paths.get_ref().as_array().ok_or_else(invalid)?;
+            match array.iter().last() {
+                Some(last) => output.insert_str(last.span().end, &format!(", {item}")),
+                None => output.insert_str(paths.span().start + 1, &item),
+            }
+        }
+        None => {
+            // right after the `[include]` header
+            let line_end = content[key.span().end..]
+                .find('\n')
+                .map(|idx| key.span().end + idx)
+                .unwrap_or(content.len());
+            output.insert_str(line_end, &format!("\npaths = [{item}]"));
+        }
+    }
+    Ok(output)
+}
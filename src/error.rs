@@ -10,7 +10,8 @@ pub enum Error {
     NoProjectsFound,
     InvalidArgs,
     UnSupportedOS,
-    GitStatus,
+    Cancelled,
+    InvalidConfig(usize),
 }
 
 impl std::fmt::Display for Error {
@@ -28,7 +29,8 @@ impl std::fmt::Display for Error {
                 write!(fmt, "No Project(s) found. Check README for more details")
             }
             Self::UnSupportedOS => write!(fmt, "Current OS is unsupported"),
-            Self::GitStatus => write!(fmt, "Something went wrong while fetching git status"),
+            Self::Cancelled => write!(fmt, "Cancelled by Ctrl-C"),
+            Self::InvalidConfig(count) => write!(fmt, "{count} problem(s) found in config"),
         }
     }
 }
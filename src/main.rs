@@ -1,121 +1,74 @@
 mod actions;
+mod config;
 mod error;
 mod tests;
 mod utils;
 
-use std::fs::read_to_string;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use actions::config_actions::{ConfigAction, ConfigCommand};
 use actions::create_layout::CreateLayout;
+use actions::exec::ExecAction;
+use actions::git_ops::{GitAction, GitCommand};
 use actions::git_status::GitStatusAction;
 use actions::list_projects::ListAction;
 use actions::main_help::MainHelpAction;
+use actions::new_project::NewProjectAction;
 use actions::open_in_nvim::OpAction;
 use actions::opinclude_actions::IncludeAction;
+use actions::reindex::ReindexAction;
+use actions::select_print::SelectPrintAction;
+use config::Config;
 use error::{Error, Result};
-use utils::constants::{
-    CONFIGFILE_COMPOUND_PROJECTS, CONFIGFILE_EXTRA_PROJECTS_ROOT, CONFIGFILE_IGNORE_DIR,
-    CONFIGFILE_INCLUDE, CONFIGFILE_PROJECTS_ROOT,
-};
-use utils::constants::{DEFAULT_IGNORE_DIR, DEFAULT_PROJECTS_ROOT, OP_CONFIG};
 use utils::create_projects_dir;
+use utils::output::OutputFormat;
+use utils::remote_url::WebLink;
 use utils::select_ui::render_loop;
-use utils::{check_help_flag, check_valid_flag, get_profile_path};
+use utils::{check_format_flag, check_help_flag, check_valid_flag};
 use utils::{ActionTrait, ShortFlag};
 
 fn main() {
     if let Err(err) = run() {
         eprintln!("{err}");
-    }
-}
-
-#[derive(Debug, Default)]
-struct Config {
-    projects_root: PathBuf,
-    extra_roots: Vec<PathBuf>,
-    ignore_dir: String,
-    include: Vec<String>,
-    compound_projects: Vec<[String; 3]>,
-}
-
-impl Config {
-    fn parse_compund_projects_config(value: &str) -> Option<[String; 3]> {
-        let x = value.split(",").collect::<Vec<_>>();
-        if x.len() != 3 {
-            return None;
-        }
-        let compound_project_name = x[0].to_owned();
-        let project_1 = x[1].to_owned();
-        let project_2 = x[2].to_owned();
-
-        Some([compound_project_name, project_1, project_2])
-    }
-
-    fn new() -> Result<Self> {
-        let home_dir = PathBuf::from(&get_profile_path()?);
-        let config_file = home_dir.join(OP_CONFIG);
-
-        let mut config = Config {
-            projects_root: home_dir.join(DEFAULT_PROJECTS_ROOT),
-            extra_roots: Vec::new(),
-            ignore_dir: DEFAULT_IGNORE_DIR.to_owned(),
-            include: Vec::new(),
-            compound_projects: Vec::new(),
-        };
-        if config_file.exists() {
-            for line in read_to_string(config_file)?.lines() {
-                if line.starts_with('#') {
-                    continue;
-                }
-                if let Some((key, value)) = line.split_once('=') {
-                    match key {
-                        CONFIGFILE_PROJECTS_ROOT => {
-                            config.projects_root = PathBuf::from(value);
-                        }
-                        CONFIGFILE_IGNORE_DIR => {
-                            config.ignore_dir = value.to_owned();
-                        }
-                        CONFIGFILE_INCLUDE => {
-                            config.include.push(value.to_owned());
-                        }
-                        CONFIGFILE_COMPOUND_PROJECTS => {
-                            if let Some(x) = Self::parse_compund_projects_config(value) {
-                                config.compound_projects.push(x);
-                            }
-                        }
-                        CONFIGFILE_EXTRA_PROJECTS_ROOT => {
-                            config.extra_roots.push(PathBuf::from(value));
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
-        Ok(config)
+        std::process::exit(1);
     }
 }
 
 #[derive(Debug, PartialEq)]
-enum ArgAction<'a> {
+enum ArgAction {
     MainHelp(MainHelpAction),
     ListAllProjects(ListAction),
-    CreateLayout(CreateLayout<'a>),
+    CreateLayout(CreateLayout),
     OpenProject(OpAction),
     AddToOpConfig(IncludeAction),
     GetGitStatus(GitStatusAction),
+    Reindex(ReindexAction),
+    Config(ConfigAction),
+    Git(GitAction),
+    Exec(ExecAction),
+    NewProject(NewProjectAction),
+    SelectPrint(SelectPrintAction),
 }
 
-impl ArgAction<'_> {
+impl ArgAction {
     fn execute(&self) -> Result<()> {
-        let config = Config::new()?;
-        match self {
-            Self::MainHelp(action) => action.execute(config),
-            Self::ListAllProjects(action) => action.execute(config),
-            Self::CreateLayout(action) => action.execute(config),
-            Self::OpenProject(action) => action.execute(config),
-            Self::AddToOpConfig(action) => action.execute(config),
-            Self::GetGitStatus(action) => action.execute(config),
-        }
+        let action: &dyn ActionTrait = match self {
+            // config actions read config files on their own & shouldn't fail on a broken config
+            Self::Config(action) => return action.execute(Config::default()),
+            Self::MainHelp(action) => action,
+            Self::ListAllProjects(action) => action,
+            Self::CreateLayout(action) => action,
+            Self::OpenProject(action) => action,
+            Self::AddToOpConfig(action) => action,
+            Self::GetGitStatus(action) => action,
+            Self::Reindex(action) => action,
+            Self::Git(action) => action,
+            Self::Exec(action) => action,
+            Self::NewProject(action) => action,
+            Self::SelectPrint(action) => action,
+        };
+        action.execute(Config::new()?)
     }
 }
 
@@ -129,7 +82,7 @@ fn run() -> Result<()> {
         if !proj_dir.try_exists()? {
             return create_projects_dir::start(proj_dir);
         }
-        render_loop(config)?;
+        render_loop(config, false)?;
     } else {
         // first arg is generally the program path and hence skipped here
         args.next();
@@ -141,6 +94,13 @@ fn run() -> Result<()> {
     Ok(())
 }
 
+fn parse_positive(arg: Option<String>) -> Result<usize> {
+    match arg.and_then(|arg| arg.parse().ok()) {
+        Some(value) if value > 0 => Ok(value),
+        _ => Err(Error::InvalidArgs),
+    }
+}
+
 fn process_arg_command<T: Iterator<Item = String>>(args: &mut T) -> Result<ArgAction> {
     // we need to have an initial arg to process it
     let arg = args.next().ok_or(Error::NoArgProvided)?;
@@ -151,7 +111,14 @@ fn process_arg_command<T: Iterator<Item = String>>(args: &mut T) -> Result<ArgAc
 
     if check_valid_flag(&arg, "list", ShortFlag::Infer)? {
         let mut list_args = ListAction::default();
-        if let Some(iarg) = &args.next() {
+        let mut next_arg = args.next();
+        if let Some(iarg) = &next_arg {
+            if let Some(format) = check_format_flag(iarg, args)? {
+                list_args.format = format;
+                next_arg = args.next();
+            }
+        }
+        if let Some(iarg) = &next_arg {
             list_args.help = check_help_flag(iarg, args)?;
         }
         return Ok(ArgAction::ListAllProjects(list_args));
@@ -159,8 +126,16 @@ fn process_arg_command<T: Iterator<Item = String>>(args: &mut T) -> Result<ArgAc
 
     if check_valid_flag(&arg, "create", ShortFlag::Infer)? {
         let mut create_args = CreateLayout::new();
-        if let Some(iarg) = &args.next() {
-            create_args.help = check_help_flag(iarg, args)?;
+        while let Some(iarg) = args.next() {
+            if check_valid_flag(&iarg, "dry-run", ShortFlag::Infer)? {
+                create_args.dry_run = true;
+            } else if check_valid_flag(&iarg, "extra-roots", ShortFlag::Infer)? {
+                create_args.extra_roots = true;
+            } else if iarg.starts_with('-') {
+                create_args.help = check_help_flag(&iarg, args)?;
+            } else {
+                create_args.lang_types.push(iarg);
+            }
         }
         return Ok(ArgAction::CreateLayout(create_args));
     }
@@ -188,18 +163,157 @@ fn process_arg_command<T: Iterator<Item = String>>(args: &mut T) -> Result<ArgAc
     }
 
     if check_valid_flag(&arg, "git-status", ShortFlag::Value('g'))? {
-        let mut git_status_args = GitStatusAction { help: false };
-        if let Some(iarg) = &args.next() {
+        let mut git_status_args = GitStatusAction::default();
+        let mut next_arg = args.next();
+        // filters & format can be given in any order
+        while let Some(iarg) = &next_arg {
+            if let Some(format) = check_format_flag(iarg, args)? {
+                git_status_args.format = format;
+            } else if check_valid_flag(iarg, "dirty", ShortFlag::Infer)? {
+                git_status_args.dirty = true;
+            } else if check_valid_flag(iarg, "ahead", ShortFlag::Infer)? {
+                git_status_args.ahead = true;
+            } else if check_valid_flag(iarg, "behind", ShortFlag::Infer)? {
+                git_status_args.behind = true;
+            } else {
+                break;
+            }
+            next_arg = args.next();
+        }
+        if let Some(iarg) = &next_arg {
             git_status_args.help = check_help_flag(iarg, args)?;
         }
         return Ok(ArgAction::GetGitStatus(git_status_args));
     }
 
+    if check_valid_flag(&arg, "reindex", ShortFlag::Infer)? {
+        let mut reindex_args = ReindexAction::default();
+        if let Some(iarg) = &args.next() {
+            reindex_args.help = check_help_flag(iarg, args)?;
+        }
+        return Ok(ArgAction::Reindex(reindex_args));
+    }
+
+    if check_valid_flag(&arg, "print", ShortFlag::Infer)? {
+        let mut print_args = SelectPrintAction::default();
+        if let Some(iarg) = &args.next() {
+            print_args.help = check_help_flag(iarg, args)?;
+        }
+        return Ok(ArgAction::SelectPrint(print_args));
+    }
+
+    if arg == "config" {
+        let mut config_args = ConfigAction {
+            command: None,
+            help: false,
+        };
+        match args.next() {
+            Some(iarg) if iarg == "migrate" || iarg == "check" => {
+                config_args.command = match iarg.as_str() {
+                    "migrate" => Some(ConfigCommand::Migrate),
+                    _ => Some(ConfigCommand::Check),
+                };
+                if let Some(iarg) = &args.next() {
+                    config_args.help = check_help_flag(iarg, args)?;
+                }
+            }
+            Some(iarg) => config_args.help = check_help_flag(&iarg, args)?,
+            None => return Err(Error::InvalidArgs),
+        }
+        return Ok(ArgAction::Config(config_args));
+    }
+
+    if arg == "git" {
+        let mut git_args = GitAction::default();
+        let mut next_arg = args.next();
+        if let Some(command) = next_arg.as_deref().and_then(GitCommand::from_name) {
+            git_args.command = Some(command);
+            next_arg = args.next();
+        }
+        while let Some(iarg) = &next_arg {
+            if check_valid_flag(iarg, "jobs", ShortFlag::Infer)? {
+                git_args.jobs = Some(parse_positive(args.next())?);
+            } else if check_valid_flag(iarg, "timeout", ShortFlag::Infer)? {
+                git_args.timeout = Duration::from_secs(parse_positive(args.next())? as u64);
+            } else {
+                break;
+            }
+            next_arg = args.next();
+        }
+        if let Some(iarg) = &next_arg {
+            git_args.help = check_help_flag(iarg, args)?;
+        }
+        // `op git --help` is the only form without a command
+        if git_args.command.is_none() && !git_args.help {
+            return Err(Error::InvalidArgs);
+        }
+        return Ok(ArgAction::Git(git_args));
+    }
+
+    if arg == "exec" {
+        let mut exec_args = ExecAction::default();
+        while let Some(iarg) = args.next() {
+            if iarg == "--" {
+                exec_args.command = args.collect();
+                break;
+            }
+            if check_valid_flag(&iarg, "filter", ShortFlag::Infer)? {
+                exec_args.filter = Some(args.next().ok_or(Error::InvalidArgs)?);
+            } else if check_valid_flag(&iarg, "lang", ShortFlag::Infer)? {
+                exec_args.lang = Some(args.next().ok_or(Error::InvalidArgs)?);
+            } else if check_valid_flag(&iarg, "jobs", ShortFlag::Infer)? {
+                exec_args.jobs = Some(parse_positive(args.next())?);
+            } else {
+                exec_args.help = check_help_flag(&iarg, args)?;
+            }
+        }
+        if exec_args.command.is_empty() && !exec_args.help {
+            return Err(Error::InvalidArgs);
+        }
+        return Ok(ArgAction::Exec(exec_args));
+    }
+
+    if arg == "new" {
+        let mut new_args = NewProjectAction::default();
+        let mut next_arg = args.next();
+        // `op new --help` is the only form without lang & name
+        match next_arg.as_deref() {
+            Some(lang) if !lang.starts_with('-') => {
+                new_args.lang = lang.to_owned();
+                new_args.name = args.next().ok_or(Error::InvalidArgs)?;
+                next_arg = args.next();
+            }
+            _ => {}
+        }
+        while let Some(iarg) = &next_arg {
+            if check_valid_flag(iarg, "template", ShortFlag::Infer)? {
+                new_args.template = Some(args.next().ok_or(Error::InvalidArgs)?);
+            } else if check_valid_flag(iarg, "git", ShortFlag::Infer)? {
+                new_args.git = true;
+            } else {
+                break;
+            }
+            next_arg = args.next();
+        }
+        if let Some(iarg) = &next_arg {
+            new_args.help = check_help_flag(iarg, args)?;
+        }
+        if new_args.name.is_empty() && !new_args.help {
+            return Err(Error::InvalidArgs);
+        }
+        return Ok(ArgAction::NewProject(new_args));
+    }
+
     // we go the OpenProject if no other flags are matched
     let mut op_args = OpAction {
         proj_name: arg,
         print_path: false,
         print_uri: false,
+        remote: None,
+        all_remotes: false,
+        web: None,
+        launch: false,
+        format: OutputFormat::Text,
         help: false,
     };
 
@@ -209,9 +323,54 @@ fn process_arg_command<T: Iterator<Item = String>>(args: &mut T) -> Result<ArgAc
         if check_valid_flag(iarg, "print", ShortFlag::Infer)? {
             op_args.print_path = true;
             next_arg = args.next();
+            // output format is only supported when printing
+            if let Some(iarg) = &next_arg {
+                if let Some(format) = check_format_flag(iarg, args)? {
+                    op_args.format = format;
+                    next_arg = args.next();
+                }
+            }
         } else if check_valid_flag(iarg, "uri", ShortFlag::Infer)? {
             op_args.print_uri = true;
             next_arg = args.next();
+            if let Some(iarg) = &next_arg {
+                if check_valid_flag(iarg, "remote", ShortFlag::Infer)? {
+                    op_args.remote = Some(args.next().ok_or(Error::InvalidArgs)?);
+                    next_arg = args.next();
+                } else if check_valid_flag(iarg, "all-remotes", ShortFlag::Infer)? {
+                    op_args.all_remotes = true;
+                    next_arg = args.next();
+                }
+            }
+        } else if check_valid_flag(iarg, "open-web", ShortFlag::Value('w'))? {
+            op_args.web = Some(WebLink::Repo);
+            next_arg = args.next();
+            // link, remote & launch flags can be given in any order
+            while let Some(iarg) = &next_arg {
+                let link = if check_valid_flag(iarg, "branch", ShortFlag::Infer)? {
+                    Some(WebLink::Branch)
+                } else if check_valid_flag(iarg, "pr", ShortFlag::Value('m'))? {
+                    Some(WebLink::PullRequest)
+                } else if check_valid_flag(iarg, "ci", ShortFlag::Infer)? {
+                    Some(WebLink::Ci)
+                } else {
+                    None
+                };
+                if let Some(link) = link {
+                    // only a single page can be linked to
+                    if op_args.web != Some(WebLink::Repo) {
+                        return Err(Error::InvalidArgs);
+                    }
+                    op_args.web = Some(link);
+                } else if check_valid_flag(iarg, "remote", ShortFlag::Infer)? {
+                    op_args.remote = Some(args.next().ok_or(Error::InvalidArgs)?);
+                } else if check_valid_flag(iarg, "launch", ShortFlag::Infer)? {
+                    op_args.launch = true;
+                } else {
+                    break;
+                }
+                next_arg = args.next();
+            }
         }
     }
 
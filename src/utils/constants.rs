@@ -3,7 +3,34 @@ pub const CONFIGFILE_EXTRA_PROJECTS_ROOT: &str = "extra_projects_root";
 pub const CONFIGFILE_IGNORE_DIR: &str = "ignore_dir";
 pub const CONFIGFILE_INCLUDE: &str = "include";
 pub const CONFIGFILE_COMPOUND_PROJECTS: &str = "compound_projects";
+pub const CONFIGFILE_EDITOR: &str = "editor";
+pub const CONFIGFILE_PROJECT_EDITOR: &str = "project_editor";
+pub const CONFIGFILE_DISCOVERY: &str = "discovery";
+pub const CONFIGFILE_MAX_DEPTH: &str = "max_depth";
+pub const CONFIGFILE_MARKERS: &str = "markers";
+pub const CONFIGFILE_TERMINAL: &str = "terminal";
+pub const CONFIGFILE_SESSIONIZER: &str = "sessionizer";
+pub const CONFIGFILE_GIT_HOST: &str = "git_host";
+pub const CONFIGFILE_LANGUAGES: &str = "languages";
 
 pub const DEFAULT_PROJECTS_ROOT: &str = "Projects";
 pub const DEFAULT_IGNORE_DIR: &str = "ignore";
+pub const DEFAULT_EDITOR: &str = "nvim .";
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+// `language_dir`s created by `--create`
+pub const DEFAULT_LANGUAGES: [&str; 5] = ["python", "javascript", "rust", "go", "plain_txt"];
+pub const DEFAULT_MARKERS: [&str; 5] = [
+    ".git",
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+];
 pub const OP_CONFIG: &str = ".opconfig";
+pub const OP_TOML_CONFIG: &str = "op.toml";
+
+pub const OP_DIR: &str = "op";
+pub const OP_INDEX: &str = "index";
+pub const OP_HISTORY: &str = "history";
+pub const OP_TEMPLATES: &str = "templates";
+pub const OP_HISTORY_LIMIT: usize = 1000;
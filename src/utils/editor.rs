@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::{Error, Result};
+use crate::Config;
+
+use super::constants::DEFAULT_EDITOR;
+
+#[derive(Debug, PartialEq)]
+pub struct Editor {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+fn split_command(template: &str) -> Vec<String> {
+    // splits a command template on whitespace while keeping quoted parts
+    // together, so `code --wait "{path}"` gives ["code", "--wait", "{path}"]
+    let mut parts = Vec::new();
+    let mut buf = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_part = false;
+
+    for ch in template.chars() {
+        match (quote, ch) {
+            (Some(q), _) if ch == q => quote = None,
+            (Some(_), _) => buf.push(ch),
+            (None, '"' | '\'') => {
+                quote = Some(ch);
+                in_part = true;
+            }
+            (None, _) if ch.is_whitespace() => {
+                if in_part {
+                    parts.push(std::mem::take(&mut buf));
+                    in_part = false;
+                }
+            }
+            (None, _) => {
+                buf.push(ch);
+                in_part = true;
+            }
+        }
+    }
+    if in_part {
+        parts.push(buf);
+    }
+    parts
+}
+
+fn env_editor(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| format!("{value} {{path}}"))
+}
+
+impl Editor {
+    pub fn from_template(template: &str, name: &str, path: &Path) -> Result<Self> {
+        let path = path.display().to_string();
+        let mut parts = split_command(template)
+            .into_iter()
+            .map(|part| part.replace("{path}", &path).replace("{name}", name));
+        let program = parts
+            .next()
+            .ok_or(Error::Any(format!("Invalid editor command '{template}'")))?;
+        Ok(Self {
+            program,
+            args: parts.collect(),
+        })
+    }
+
+    /// Picks the editor command for a project in the below order
+    /// `project_editor` override > `editor` > $VISUAL > $EDITOR > nvim
+    pub fn resolve(config: &Config, name: &str, path: &Path) -> Result<Self> {
+        let template = config
+            .project_editors
+            .iter()
+            .find(|f| f[0] == name)
+            .map(|f| f[1].to_owned())
+            .or_else(|| config.editor.clone())
+            .or_else(|| env_editor("VISUAL"))
+            .or_else(|| env_editor("EDITOR"))
+            .unwrap_or_else(|| DEFAULT_EDITOR.to_owned());
+        Self::from_template(&template, name, path)
+    }
+
+    fn exists(&self) -> bool {
+        // program can either be a path to an executable or a name in PATH
+        let program = PathBuf::from(&self.program);
+        if program.components().count() > 1 {
+            return program.exists();
+        }
+        exec_check::executable_exists(&self.program)
+    }
+
+    fn ensure_exists(&self) -> Result<()> {
+        self.exists()
+            .then_some(())
+            .ok_or(Error::Any(format!("Missing {} executable", self.program)))
+    }
+
+    /// Program followed by its args, e.g to be run by a terminal multiplexer
+    pub fn command_line(&self) -> Result<Vec<&str>> {
+        self.ensure_exists()?;
+        Ok(std::iter::once(&self.program)
+            .chain(&self.args)
+            .map(|part| part.as_str())
+            .collect())
+    }
+
+    pub fn launch(&self, path: &Path) -> Result<()> {
+        self.ensure_exists()?;
+
+        std::env::set_current_dir(path)?;
+        Command::new(&self.program).args(&self.args).status()?;
+        Ok(())
+    }
+}
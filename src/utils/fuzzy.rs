@@ -1,10 +1,39 @@
-use std::char::ToLowercase;
 use std::cmp::max;
 
-pub fn scored_fuzzy_search(pattern: &'_ str, st: &'_ str) -> (bool, i64) {
+use console::Style;
+
+/// Whether `pattern` matches `st`, its score & char positions of the matched
+/// characters in `st`
+pub type FuzzyMatch = (bool, i64, Vec<usize>);
+
+/// Letters are compared by their lower case forms unless `case_sensitive`
+pub fn same_letter(a: char, b: char, case_sensitive: bool) -> bool {
+    a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()))
+}
+
+/// Upper case letter, like in camelCase. Characters without case are neither
+fn is_upper(ch: char) -> bool {
+    ch.to_uppercase().eq([ch]) && !ch.to_lowercase().eq([ch])
+}
+
+fn is_lower(ch: char) -> bool {
+    ch.to_lowercase().eq([ch]) && !ch.to_uppercase().eq([ch])
+}
+
+pub fn scored_fuzzy_search(pattern: &'_ str, st: &'_ str) -> FuzzyMatch {
+    scored_fuzzy_search_cased(pattern, st, false)
+}
+
+/// Same as `scored_fuzzy_search`, letters of a different case don't match if
+/// `case_sensitive`
+pub fn scored_fuzzy_search_cased(
+    pattern: &'_ str,
+    st: &'_ str,
+    case_sensitive: bool,
+) -> FuzzyMatch {
     // https://github.com/tajmone/fuzzy-search
-    // 1:1 port of python implementation in above project
-    // Don't know if this can be improved, but works for now.
+    // Based on the python implementation in above project. Both strings are
+    // walked once by chars, so indices & the lead penalty count chars not bytes
     //
     let separator_bonus = 10;
     let adj_bonus = 5;
@@ -14,58 +43,41 @@ pub fn scored_fuzzy_search(pattern: &'_ str, st: &'_ str) -> (bool, i64) {
     let lead_penalty = -3;
     let max_lead_penalty = -9;
 
+    // next pattern char to be matched
+    let mut pattern = pattern.chars().peekable();
     let mut p_idx = 0_usize;
-    let (mut s_idx, p_len, s_len) = (0, pattern.len(), st.len());
     let (mut prev_match, mut prev_lower) = (false, false);
     let mut prev_sep = true;
 
-    let mut best_letter: Option<char> = None;
-    let mut best_lower: Option<ToLowercase> = None;
-    let mut best_letter_idx: Option<usize> = None;
+    // best candidate for the last matched pattern char & its position
+    let mut best_letter: Option<(char, usize)> = None;
     let mut best_letter_score = 0;
+    // a match without any bonus never becomes the best letter, its position
+    // is kept here so it's still highlighted
+    let mut unscored_idx: Option<usize> = None;
     let mut matched_indices: Vec<usize> = Vec::new();
 
     let mut score = 0;
 
-    while s_idx < s_len {
-        let p_char = pattern.chars().nth(p_idx);
-        let p_lower = p_char.map(|val| val.to_lowercase().to_string());
-        let s_char = st.chars().nth(s_idx).unwrap();
-        let s_lower = s_char.to_lowercase().to_string();
-        let s_upper = s_char.to_uppercase().to_string();
-
-        let next_match = p_char.is_some()
-            && if let Some(val) = &p_lower {
-                val == &s_lower.to_string()
-            } else {
-                false
-            };
-
-        let rematch = if let Some(b_val) = &best_lower {
-            if let Some(p_val) = &p_lower {
-                p_val == &b_val.to_string()
-            } else {
-                false
-            }
-        } else {
-            false
-        };
+    for (s_idx, s_char) in st.chars().enumerate() {
+        let p_char = pattern.peek().copied();
 
+        let next_match = p_char.is_some_and(|p_char| same_letter(p_char, s_char, case_sensitive));
+        // pattern char is the same as the best letter, like `l` in `ll`
+        let p_repeat = match (best_letter, p_char) {
+            (Some((best, _)), Some(p_char)) => same_letter(best, p_char, case_sensitive),
+            _ => false,
+        };
         let advanced = next_match && best_letter.is_some();
-        let p_repeat = best_letter.is_some()
-            && p_char.is_some()
-            && best_lower.clone().unwrap().to_string() == p_lower.unwrap();
 
         if advanced || p_repeat {
             score += best_letter_score;
-            matched_indices.push(best_letter_idx.unwrap());
-            best_letter = None;
-            best_lower = None;
-            best_letter_idx = None;
+            matched_indices.extend(best_letter.take().map(|(_, idx)| idx));
             best_letter_score = 0;
         }
 
-        if next_match || rematch {
+        if next_match || p_repeat {
+            matched_indices.extend(unscored_idx.take());
             let mut new_score = 0;
 
             if p_idx == 0 {
@@ -80,11 +92,12 @@ pub fn scored_fuzzy_search(pattern: &'_ str, st: &'_ str) -> (bool, i64) {
                 new_score += separator_bonus;
             }
 
-            if prev_lower && s_char.to_string() == s_upper && s_lower != s_upper {
+            if prev_lower && is_upper(s_char) {
                 new_score += camel_case_bonus;
             }
 
             if next_match {
+                pattern.next();
                 p_idx += 1;
             }
 
@@ -92,10 +105,10 @@ pub fn scored_fuzzy_search(pattern: &'_ str, st: &'_ str) -> (bool, i64) {
                 if best_letter.is_some() {
                     score += unmatched_penalty;
                 }
-                best_letter = Some(s_char);
-                best_lower = Some(s_char.to_lowercase());
-                best_letter_idx = Some(s_idx);
+                best_letter = Some((s_char, s_idx));
                 best_letter_score = new_score;
+            } else if best_letter.is_none() {
+                unscored_idx = Some(s_idx);
             }
             prev_match = true;
         } else {
@@ -103,15 +116,35 @@ pub fn scored_fuzzy_search(pattern: &'_ str, st: &'_ str) -> (bool, i64) {
             prev_match = false;
         }
 
-        prev_lower = s_char.to_string() == s_lower && s_lower != s_upper;
+        prev_lower = is_lower(s_char);
         prev_sep = "_ ".contains(s_char);
-
-        s_idx += 1;
     }
-    if best_letter.is_some() {
+    matched_indices.extend(unscored_idx);
+    if let Some((_, idx)) = best_letter {
         score += best_letter_score;
-        matched_indices.push(best_letter_idx.unwrap());
+        matched_indices.push(idx);
     }
 
-    (p_idx == p_len, score)
+    (pattern.peek().is_none(), score, matched_indices)
+}
+
+/// `item` styled with `base`, characters at char positions in `indices` are
+/// highlighted on top of it
+pub fn highlight_matches(item: &str, indices: &[usize], base: &Style) -> String {
+    let highlight = base.clone().yellow().bold();
+    // consecutive characters with the same style are styled together
+    let mut runs: Vec<(bool, String)> = Vec::new();
+    for (idx, ch) in item.chars().enumerate() {
+        let matched = indices.contains(&idx);
+        match runs.last_mut() {
+            Some((run_matched, run)) if *run_matched == matched => run.push(ch),
+            _ => runs.push((matched, ch.to_string())),
+        }
+    }
+    runs.into_iter()
+        .map(|(matched, run)| match matched {
+            true => highlight.apply_to(run).to_string(),
+            false => base.apply_to(run).to_string(),
+        })
+        .collect()
 }
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::constants::{OP_HISTORY, OP_HISTORY_LIMIT};
+use super::{get_data_dir, write_atomic};
+use crate::error::Result;
+
+////////////////////////////////////////////////////////////////////////////////
+// History of opened projects used for frecency ranking
+////////////////////////////////////////////////////////////////////////////////
+//
+// Every open is stored as a `<unix_timestamp>\t<project_path>` line. Only the
+// latest `OP_HISTORY_LIMIT` opens are kept around
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+const MONTH: u64 = 30 * DAY;
+
+#[derive(Debug, Default)]
+pub struct History {
+    entries: Vec<(u64, PathBuf)>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn visit_weight(age: u64) -> i64 {
+    // recent visits count more than older ones
+    match age {
+        a if a < HOUR => 100,
+        a if a < DAY => 70,
+        a if a < WEEK => 50,
+        a if a < MONTH => 30,
+        _ => 10,
+    }
+}
+
+/// Converts a frecency score into a bonus which can be added to a fuzzy
+/// score. Grows logarithmically so a well matched pattern still wins over a
+/// frequently opened project
+pub fn frecency_bonus(frecency: i64) -> i64 {
+    ((frecency.max(0) as f64).ln_1p() * 3.0).round() as i64
+}
+
+impl History {
+    fn history_path() -> Result<PathBuf> {
+        Ok(get_data_dir()?.join(OP_HISTORY))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let entries = content
+            .lines()
+            .filter_map(|line| {
+                let (timestamp, path) = line.split_once('\t')?;
+                Some((timestamp.parse().ok()?, PathBuf::from(path)))
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn load() -> Self {
+        Self::history_path()
+            .ok()
+            .and_then(|path| read_to_string(path).ok())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// Adds an entry dropping the oldest ones over `OP_HISTORY_LIMIT`
+    pub fn push(&mut self, timestamp: u64, path: &Path) {
+        self.entries.push((timestamp, path.to_path_buf()));
+        let overflow = self.entries.len().saturating_sub(OP_HISTORY_LIMIT);
+        self.entries.drain(..overflow);
+    }
+
+    pub fn to_content(&self) -> String {
+        let mut output = String::new();
+        for (timestamp, path) in &self.entries {
+            output.push_str(&format!("{}\t{}\n", timestamp, path.display()));
+        }
+        output
+    }
+
+    /// Adds an entry for `path` opened just now
+    pub fn record(path: &Path) -> Result<()> {
+        let mut history = Self::load();
+        history.push(now(), path);
+        write_atomic(&Self::history_path()?, &history.to_content())
+    }
+
+    /// Frecency score of every project present in history
+    pub fn scores(&self) -> HashMap<PathBuf, i64> {
+        self.scores_at(now())
+    }
+
+    /// Frecency scores as of `now`, a unix timestamp
+    pub fn scores_at(&self, now: u64) -> HashMap<PathBuf, i64> {
+        let mut scores = HashMap::new();
+        for (timestamp, path) in &self.entries {
+            let weight = visit_weight(now.saturating_sub(*timestamp));
+            *scores.entry(path.to_owned()).or_insert(0) += weight;
+        }
+        scores
+    }
+}
@@ -0,0 +1,285 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use walkdir::WalkDir;
+
+use super::constants::OP_INDEX;
+use super::{get_cache_dir, write_atomic};
+use crate::error::Result;
+use crate::Config;
+
+////////////////////////////////////////////////////////////////////////////////
+// On disk index of the projects found under each root
+////////////////////////////////////////////////////////////////////////////////
+//
+// Layout of the index file, one entry per line. Paths are always the last
+// column so they can contain any character except a newline
+//
+// root    <key>   <root_path>
+// watch   <mtime> <dir_path>
+// project <project_path>
+// end     <number_of_lines_above>
+//
+// `watch` lines hold modification times of every directory walked while
+// scanning a root. Adding, removing or renaming a project changes the mtime of
+// its parent, so a root is only rescanned when one of them differs.
+//
+// A file without the `end` line or with a different count is cut short and
+// ignored, like a missing index.
+
+const INDEX_HEADER: &str = "# op index v2";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DiscoveryMode {
+    /// projects are exactly at `root/<lang>/<project>`
+    #[default]
+    Layout,
+    /// projects are directories containing a marker file, e.g `.git`
+    Markers,
+}
+
+#[derive(Debug, Default)]
+struct RootIndex {
+    root: PathBuf,
+    // settings which change the result of a scan, e.g ignore dir
+    key: String,
+    watched: Vec<(PathBuf, u128)>,
+    projects: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProjectIndex {
+    roots: Vec<RootIndex>,
+    dirty: bool,
+}
+
+fn modified(path: &Path) -> Option<u128> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+impl RootIndex {
+    fn watch(&mut self, dir: PathBuf) {
+        if let Some(mtime) = modified(&dir) {
+            self.watched.push((dir, mtime));
+        }
+    }
+
+    fn scan_layout(&mut self, ignore_path: &Path) -> std::io::Result<()> {
+        // every directory at `root/<lang>/<project>` level is a project
+        for entry in WalkDir::new(&self.root).max_depth(2) {
+            let entry = entry?;
+            if entry.path().starts_with(ignore_path) {
+                continue;
+            }
+            if entry.depth() == 2 {
+                self.projects.push(entry.into_path());
+            } else if entry.file_type().is_dir() {
+                self.watch(entry.into_path());
+            }
+        }
+        Ok(())
+    }
+
+    fn scan_markers(
+        &mut self,
+        ignore_path: &Path,
+        max_depth: usize,
+        markers: &[String],
+    ) -> std::io::Result<()> {
+        // any directory upto `max_depth` containing one of the `markers` is a
+        // project. Directories inside a project are not walked any further.
+        // Projects are watched too, adding or removing a marker changes their mtime
+        let mut walker = WalkDir::new(&self.root).max_depth(max_depth).into_iter();
+        while let Some(entry) = walker.next() {
+            let entry = entry?;
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            if entry.path().starts_with(ignore_path) {
+                walker.skip_current_dir();
+                continue;
+            }
+            let is_project = markers
+                .iter()
+                .any(|marker| entry.path().join(marker).exists());
+            if entry.depth() > 0 && is_project {
+                self.watch(entry.path().to_path_buf());
+                self.projects.push(entry.into_path());
+                walker.skip_current_dir();
+            } else {
+                self.watch(entry.into_path());
+            }
+        }
+        Ok(())
+    }
+
+    fn scan(root: &Path, config: &Config) -> std::io::Result<Self> {
+        let mut root_index = Self {
+            root: root.to_path_buf(),
+            key: Self::key(config),
+            ..Default::default()
+        };
+        let ignore_path = root.join(&config.ignore_dir);
+        match config.discovery {
+            DiscoveryMode::Layout => root_index.scan_layout(&ignore_path)?,
+            DiscoveryMode::Markers => root_index.scan_markers(
+                &ignore_path,
+                config.max_depth,
+                &config.discovery_markers(),
+            )?,
+        }
+        Ok(root_index)
+    }
+
+    fn key(config: &Config) -> String {
+        match config.discovery {
+            DiscoveryMode::Layout => config.ignore_dir.to_owned(),
+            DiscoveryMode::Markers => format!(
+                "{}|markers|{}|{}",
+                config.ignore_dir,
+                config.max_depth,
+                config.discovery_markers().join(",")
+            ),
+        }
+    }
+
+    fn is_fresh(&self) -> bool {
+        !self.watched.is_empty()
+            && self
+                .watched
+                .iter()
+                .all(|(dir, mtime)| modified(dir) == Some(*mtime))
+    }
+}
+
+impl ProjectIndex {
+    fn index_path() -> Result<PathBuf> {
+        Ok(get_cache_dir()?.join(OP_INDEX))
+    }
+
+    /// `None` unless `content` is a complete index
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut lines = content.lines();
+        if lines.next()? != INDEX_HEADER {
+            return None;
+        }
+
+        let mut roots: Vec<RootIndex> = Vec::new();
+        let mut count = 0;
+        let mut complete = false;
+        for line in lines {
+            if complete {
+                return None;
+            }
+            let (kind, value) = line.split_once('\t')?;
+            match kind {
+                "end" => {
+                    complete = value.parse::<usize>().ok()? == count;
+                    if !complete {
+                        return None;
+                    }
+                    continue;
+                }
+                "root" => {
+                    let (key, root) = value.split_once('\t')?;
+                    roots.push(RootIndex {
+                        root: PathBuf::from(root),
+                        key: key.to_owned(),
+                        ..Default::default()
+                    });
+                }
+                "watch" => {
+                    let (mtime, dir) = value.split_once('\t')?;
+                    let mtime = mtime.parse().ok()?;
+                    roots.last_mut()?.watched.push((PathBuf::from(dir), mtime));
+                }
+                "project" => roots.last_mut()?.projects.push(PathBuf::from(value)),
+                _ => return None,
+            }
+            count += 1;
+        }
+        complete.then_some(Self {
+            roots,
+            dirty: false,
+        })
+    }
+
+    pub fn to_content(&self) -> String {
+        let mut lines = Vec::new();
+        for root in &self.roots {
+            lines.push(format!("root\t{}\t{}", root.key, root.root.display()));
+            for (dir, mtime) in &root.watched {
+                lines.push(format!("watch\t{}\t{}", mtime, dir.display()));
+            }
+            for project in &root.projects {
+                lines.push(format!("project\t{}", project.display()));
+            }
+        }
+        let count = lines.len();
+        let mut output = String::new();
+        output.push_str(INDEX_HEADER);
+        output.push('\n');
+        for line in lines {
+            output.push_str(&line);
+            output.push('\n');
+        }
+        output.push_str(&format!("end\t{count}\n"));
+        output
+    }
+
+    /// Loads the index from cache dir. A missing or unreadable index is
+    /// treated as empty and every root gets scanned again
+    pub fn load() -> Self {
+        Self::index_path()
+            .ok()
+            .and_then(|path| read_to_string(path).ok())
+            .and_then(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        write_atomic(&Self::index_path()?, &self.to_content())
+    }
+
+    /// Returns projects under `root`, rescanning it only if the index is
+    /// stale or `force` is set
+    pub fn projects(
+        &mut self,
+        root: &Path,
+        config: &Config,
+        force: bool,
+    ) -> std::io::Result<Vec<PathBuf>> {
+        let key = RootIndex::key(config);
+        let position = self
+            .roots
+            .iter()
+            .position(|f| f.root == root && f.key == key);
+
+        if let Some(idx) = position {
+            if !force && self.roots[idx].is_fresh() {
+                return Ok(self.roots[idx].projects.clone());
+            }
+        }
+
+        let root_index = RootIndex::scan(root, config)?;
+        let projects = root_index.projects.clone();
+        match position {
+            Some(idx) => self.roots[idx] = root_index,
+            None => self.roots.push(root_index),
+        }
+        self.dirty = true;
+        Ok(projects)
+    }
+
+    /// Drops roots which are no longer part of the config
+    pub fn retain_roots(&mut self, roots: &[&PathBuf]) {
+        let before = self.roots.len();
+        self.roots.retain(|f| roots.contains(&&f.root));
+        self.dirty |= before != self.roots.len();
+    }
+}
@@ -1,18 +1,32 @@
 pub mod constants;
 pub mod create_projects_dir;
+pub mod editor;
 pub mod fuzzy;
+pub mod history;
+pub mod index;
+pub mod output;
+pub mod process;
 pub mod projects;
+pub mod query;
+pub mod remote_url;
+pub mod runner;
 pub mod select_ui;
+pub mod terminal;
 
+use output::OutputFormat;
 use projects::Projects;
-use std::{env::consts::OS, path::PathBuf};
+use std::{
+    env::consts::OS,
+    fs::DirBuilder,
+    path::{Path, PathBuf},
+};
 
 use crate::{
     error::{Error, Result},
     Config,
 };
 
-use self::constants::OP_CONFIG;
+use self::constants::{OP_CONFIG, OP_DIR};
 
 pub trait ActionTrait {
     fn execute(&self, config: Config) -> Result<()>;
@@ -61,6 +75,18 @@ pub fn check_help_flag<T: Iterator<Item = String>>(arg: &String, args: &mut T) -
     Err(Error::InvalidArgs)
 }
 
+/// Checks for `--format|-f <format>` and consumes the format from `args`
+pub fn check_format_flag<T: Iterator<Item = String>>(
+    arg: &String,
+    args: &mut T,
+) -> Result<Option<OutputFormat>> {
+    if !check_valid_flag(arg, "format", ShortFlag::Infer)? {
+        return Ok(None);
+    }
+    let format = args.next().ok_or(Error::InvalidArgs)?;
+    Ok(Some(OutputFormat::from_name(&format)?))
+}
+
 pub fn get_profile_path() -> Result<String> {
     match OS {
         "windows" => Ok(std::env::var("userprofile")?),
@@ -82,6 +108,19 @@ pub fn validate_paths(paths: &Vec<String>) -> Vec<PathBuf> {
     include_paths
 }
 
+/// A single path component, so nothing is created outside of the parent dir
+pub fn validate_dir_name(kind: &str, name: &str) -> Result<()> {
+    let invalid = name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains(['/', '\\'])
+        || Path::new(name).is_absolute();
+    if invalid {
+        return Err(Error::Any(format!("Invalid {kind} name '{name}'")));
+    }
+    Ok(())
+}
+
 pub fn get_projects(config: Config) -> Result<Projects> {
     let proj_dir = &config.projects_root;
     if !proj_dir.try_exists()? {
@@ -95,3 +134,53 @@ pub fn get_config_path() -> Result<PathBuf> {
     let config_file = home_dir.join(OP_CONFIG);
     Ok(config_file)
 }
+
+fn get_xdg_dir(xdg_var: &str, windows_var: &str, home_fallback: &str) -> Result<PathBuf> {
+    // $XDG_* is respected on every OS, otherwise falls back to the platform default
+    if let Ok(dir) = std::env::var(xdg_var) {
+        if !dir.is_empty() {
+            return Ok(PathBuf::from(dir).join(OP_DIR));
+        }
+    }
+    match OS {
+        "windows" => Ok(PathBuf::from(std::env::var(windows_var)?).join(OP_DIR)),
+        _ => Ok(PathBuf::from(get_profile_path()?)
+            .join(home_fallback)
+            .join(OP_DIR)),
+    }
+}
+
+pub fn get_cache_dir() -> Result<PathBuf> {
+    get_xdg_dir("XDG_CACHE_HOME", "localappdata", ".cache")
+}
+
+pub fn get_data_dir() -> Result<PathBuf> {
+    get_xdg_dir("XDG_DATA_HOME", "localappdata", ".local/share")
+}
+
+pub fn get_config_dir() -> Result<PathBuf> {
+    get_xdg_dir("XDG_CONFIG_HOME", "appdata", ".config")
+}
+
+/// Replaces `path` with `content` at once, creating its parent dirs. Content
+/// is written to a sibling temp file first, so a concurrent `op` never reads a
+/// half written file
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        DirBuilder::new().recursive(true).create(parent)?;
+    }
+    let file_name = path
+        .file_name()
+        .ok_or(Error::Any(format!("Invalid file path {}", path.display())))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    std::fs::write(&temp_path, content)?;
+    if let Err(err) = std::fs::rename(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(err.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use crate::error::{Error, Result};
+
+////////////////////////////////////////////////////////////////////////////////
+// Machine readable output for `--format json|tsv`
+////////////////////////////////////////////////////////////////////////////////
+//
+// Every command emits a list of records with the same fields in the same order,
+// so json is an array of objects and tsv has a header row followed by a row
+// per record
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Tsv,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "tsv" => Ok(Self::Tsv),
+            _ => Err(Error::Any(format!(
+                "Unknown format '{name}'. Should be one of text, json or tsv"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    Null,
+}
+
+impl From<&str> for Field {
+    fn from(value: &str) -> Self {
+        Self::Str(value.to_owned())
+    }
+}
+
+impl From<String> for Field {
+    fn from(value: String) -> Self {
+        Self::Str(value)
+    }
+}
+
+impl From<i64> for Field {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<bool> for Field {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl<T: Into<Field>> From<Option<T>> for Field {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+/// Field names along with their values, in output order
+pub type Record = Vec<(&'static str, Field)>;
+
+fn json_string(value: &str) -> String {
+    let mut output = String::from('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+fn json_field(field: &Field) -> String {
+    match field {
+        Field::Str(value) => json_string(value),
+        Field::Int(value) => value.to_string(),
+        Field::Bool(value) => value.to_string(),
+        Field::Null => "null".to_owned(),
+    }
+}
+
+fn tsv_field(field: &Field) -> String {
+    // tabs & newlines would break the row, so they are escaped
+    match field {
+        Field::Str(value) => value
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r"),
+        Field::Int(value) => value.to_string(),
+        Field::Bool(value) => value.to_string(),
+        Field::Null => String::new(),
+    }
+}
+
+/// Array of objects, one object per line
+pub fn to_json(records: &[Record]) -> String {
+    let objects = records
+        .iter()
+        .map(|record| {
+            let fields = record
+                .iter()
+                .map(|(key, value)| format!("{}: {}", json_string(key), json_field(value)))
+                .collect::<Vec<_>>();
+            format!("  {{{}}}", fields.join(", "))
+        })
+        .collect::<Vec<_>>();
+    match objects.is_empty() {
+        true => "[]".to_owned(),
+        false => format!("[\n{}\n]", objects.join(",\n")),
+    }
+}
+
+/// Header row with `keys` followed by a row per record
+pub fn to_tsv(keys: &[&str], records: &[Record]) -> String {
+    let mut rows = vec![keys.join("\t")];
+    for record in records {
+        let row = record
+            .iter()
+            .map(|(_, value)| tsv_field(value))
+            .collect::<Vec<_>>();
+        rows.push(row.join("\t"));
+    }
+    rows.join("\n")
+}
+
+/// Prints records in the given machine readable format. `keys` are only used
+/// for the tsv header, so it is printed even without any records
+pub fn print_records(format: OutputFormat, keys: &[&str], records: &[Record]) {
+    match format {
+        OutputFormat::Json => println!("{}", to_json(records)),
+        OutputFormat::Tsv => println!("{}", to_tsv(keys, records)),
+        OutputFormat::Text => {}
+    }
+}
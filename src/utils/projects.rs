@@ -1,9 +1,19 @@
-use std::path::PathBuf;
-use std::process::Command;
-use walkdir::WalkDir;
-
-use super::fuzzy::scored_fuzzy_search;
+use std::cmp::{max, Reverse};
+use std::collections::HashMap;
+use std::iter::once;
+use std::path::{Path, PathBuf};
+
+use console::Style;
+
+use super::editor::Editor;
+use super::fuzzy::highlight_matches;
+use super::history::{frecency_bonus, History};
+use super::index::ProjectIndex;
+use super::output::{Field, Record};
+use super::query::{Query, QueryMatch};
+use super::terminal::{tmux_sessionize, TabProject, TermEnv};
 use super::validate_paths;
+use crate::config::CompoundProject;
 use crate::error::Error;
 use crate::{Config, Result};
 
@@ -13,13 +23,42 @@ pub struct Projects {
     pub dir_items: Vec<PathBuf>,
     // pub filtered_items: Vec<PathBuf>,
     pub filtered_items: Vec<String>,
-    // to be used for selecting ui i.e running command without any args
-    cli_no_arg: bool,
-    // for buffered stdout
-    pub buffer_rows: usize,
+    // char positions of the filter in `filtered_items`, set by the select UI
+    pub matched_indices: HashMap<String, Vec<usize>>,
+    // unique display id of every project in `dir_items`
+    ids: HashMap<PathBuf, String>,
+    // frecency score of projects by id, only loaded for select UI
+    frecency: HashMap<String, i64>,
     config: Config,
 }
 
+/// Where a project was found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectSource {
+    Root,
+    ExtraRoot,
+    Include,
+    Compound,
+}
+
+impl ProjectSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Root => "root",
+            Self::ExtraRoot => "extra_root",
+            Self::Include => "include",
+            Self::Compound => "compound",
+        }
+    }
+}
+
+/// Name of the compound project opened with projects marked in select UI,
+/// like the tmux session
+const SELECTION_NAME: &str = "op";
+
+/// Fields of every record in machine readable project listings
+pub const PROJECT_RECORD_KEYS: [&str; 4] = ["name", "path", "source", "id"];
+
 fn get_file_name(file: &PathBuf) -> String {
     let file_name = file
         .file_name()
@@ -29,69 +68,141 @@ fn get_file_name(file: &PathBuf) -> String {
     file_name.to_owned()
 }
 
+fn path_parts(path: &Path) -> Vec<String> {
+    // components from file name upwards
+    path.components()
+        .rev()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Display id of every project. It is the name of the project if that is
+/// unique, otherwise as many parent dirs as needed are prefixed to tell projects
+/// apart, like `rust/utils` & `go/utils`
+pub fn unique_ids(paths: &[PathBuf]) -> HashMap<PathBuf, String> {
+    let mut by_name: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+    for path in paths {
+        let parts = path_parts(path);
+        by_name.entry(parts[0].clone()).or_default().push(parts);
+    }
+
+    let mut ids = HashMap::new();
+    for (path, parts) in paths.iter().map(|p| (p, path_parts(p))) {
+        let same_name = &by_name[&parts[0]];
+        let mut depth = 1;
+        while depth < parts.len()
+            && same_name
+                .iter()
+                .filter(|other| other.len() >= depth && other[..depth] == parts[..depth])
+                .count()
+                > 1
+        {
+            depth += 1;
+        }
+        let id = parts[..depth]
+            .iter()
+            .rev()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("/");
+        ids.insert(path.clone(), id);
+    }
+    ids
+}
+
 impl Projects {
-    fn get_list(project_path: &PathBuf, ignore_path: &PathBuf) -> std::io::Result<Vec<PathBuf>> {
-        let mut projs_vec = Vec::<PathBuf>::new();
-        for entry in WalkDir::new(project_path).max_depth(2) {
-            let entry = entry?;
-            if entry.depth() == 2 && !entry.path().starts_with(ignore_path) {
-                projs_vec.push(entry.into_path());
-            }
+    /// Projects found under `projects_root` and every `extra_projects_root`.
+    /// Roots are only walked again if `index` is stale for them or `force` is set
+    pub fn get_root_items(
+        config: &Config,
+        index: &mut ProjectIndex,
+        force: bool,
+    ) -> Result<Vec<PathBuf>> {
+        let roots = once(&config.projects_root)
+            .chain(config.extra_roots.iter())
+            .collect::<Vec<_>>();
+
+        let mut dir_items = Vec::new();
+        for root in &roots {
+            dir_items.extend(index.projects(root, config, force)?);
         }
-        Ok(projs_vec)
+        index.retain_roots(&roots);
+        Ok(dir_items)
     }
 
     pub fn new(config: Config, cli_no_arg: bool) -> Result<Self> {
         let include_paths = validate_paths(&config.include);
 
-        // from configuration `project_root`
-        let ignore_path = config.projects_root.join(&config.ignore_dir);
-        let mut dir_items = Self::get_list(&config.projects_root, &ignore_path)?;
-
-        // from configuration `extra_project_root`s
-        for extra_project_root in &config.extra_roots {
-            let ignore_path = extra_project_root.join(&config.ignore_dir);
-            dir_items.extend(Self::get_list(extra_project_root, &ignore_path)?);
-        }
+        // from configuration `project_root` & `extra_project_root`s
+        let mut index = ProjectIndex::load();
+        let mut dir_items = Self::get_root_items(&config, &mut index, false)?;
+        // best effort, failing to write the cache shouldn't stop listing projects
+        let _ = index.save();
 
         // from the configuration `include`s
         for path in include_paths.into_iter() {
             if dir_items.contains(&path) {
                 eprintln!("WARNING: {path:?} is already tracked. Consider removing it from config");
+                continue;
             }
             dir_items.push(path)
         }
 
+        // frecency only ranks the select UI
+        let scores = match cli_no_arg {
+            true => History::load().scores(),
+            false => HashMap::new(),
+        };
+        Ok(Self::from_items(config, dir_items, &scores))
+    }
+
+    /// Projects at `dir_items` paths along with compound projects of `config`.
+    /// Projects with higher frecency `scores` are listed first
+    pub fn from_items(
+        config: Config,
+        mut dir_items: Vec<PathBuf>,
+        scores: &HashMap<PathBuf, i64>,
+    ) -> Self {
         dir_items.sort_by(|a, b| {
             let file_a = get_file_name(a).to_lowercase();
             let file_b = get_file_name(b).to_lowercase();
             file_a.cmp(&file_b)
         });
 
+        let ids = unique_ids(&dir_items);
         let mut filtered_items = dir_items
-            .clone()
             .iter()
-            .map(get_file_name)
+            .map(|item| ids[item].clone())
             .collect::<Vec<_>>();
 
         let compound_projects = config
             .compound_projects
             .iter()
-            .map(|s| s[0].clone())
+            .map(|s| s.name.clone())
             .collect::<Vec<_>>();
 
         filtered_items.extend(compound_projects);
         filtered_items.sort();
 
-        let projects = Self {
+        let mut frecency = HashMap::new();
+        for item in &dir_items {
+            if let Some(score) = scores.get(item) {
+                let entry = frecency.entry(ids[item].clone()).or_insert(0);
+                *entry = max(*entry, *score);
+            }
+        }
+        // most used projects first, alphabetical order is kept for the rest
+        filtered_items.sort_by_key(|item| Reverse(frecency.get(item).copied()));
+
+        Self {
             selected_idx: 0,
             filtered_items,
+            matched_indices: HashMap::new(),
             dir_items,
-            cli_no_arg,
-            buffer_rows: 10,
+            ids,
+            frecency,
             config,
-        };
-        Ok(projects)
+        }
     }
 
     pub fn catch_empty_project_list(self) -> Result<Self> {
@@ -102,95 +213,195 @@ impl Projects {
         }
     }
 
-    pub fn filter_project_list(&mut self, filter_string: &str) -> Vec<(String, (bool, i64))> {
+    pub fn filter_project_list(&mut self, filter_string: &str) -> Vec<(String, QueryMatch)> {
+        let query = Query::parse(filter_string);
         let mut project_list = self
             .dir_items
             .iter()
-            .map(|item| {
-                let f_name = get_file_name(item);
-                let fuz = scored_fuzzy_search(filter_string, &f_name.to_lowercase());
-                (f_name, fuz)
+            .filter_map(|item| {
+                let id = self.project_id(item);
+                let lang = self.project_lang(item);
+                let found = query.matches(&id, lang.as_deref());
+                found.map(|found| (id, found))
             })
-            .filter(|item| item.1 .0)
             .collect::<Vec<_>>();
 
         let compound_list = self
             .config
             .compound_projects
             .iter()
-            .map(|item| {
-                let cp_name = item[0].to_lowercase();
-                let fuz = scored_fuzzy_search(filter_string, &cp_name);
-                (cp_name, fuz)
+            .filter_map(|item| {
+                let found = query.matches(&item.name, None);
+                found.map(|found| (item.name.clone(), found))
             })
-            .filter(|item| item.1 .0)
             .collect::<Vec<_>>();
 
         project_list.extend(compound_list);
 
-        project_list.sort_by(|a, b| {
-            let a_fuz = b.1;
-            let b_fuz = a.1;
-            a_fuz.1.cmp(&b_fuz.1)
+        // query score blended with how often & recently a project was opened
+        project_list.sort_by_cached_key(|(name, found)| {
+            let frecency = self.frecency.get(name).copied().unwrap_or_default();
+            Reverse(found.0 + frecency_bonus(frecency))
         });
         project_list
     }
 
-    pub fn print_project_path(&self, project_name: &str) -> Option<String> {
+    pub fn print_project_path(&self, project_name: &str) -> Result<Option<String>> {
         let mut output = String::new();
 
-        let compound_project_names = self
-            .config
-            .compound_projects
-            .iter()
-            .filter(|f| f[0] == project_name)
-            .collect::<Vec<_>>();
-
-        if !compound_project_names.is_empty() {
+        if let Some(compound_project) = self.compound_project(project_name) {
             output.push_str("Compound Project:");
             output.push('\n');
             output.push_str("-----------------");
             output.push('\n');
 
-            for proj in &self.dir_items {
-                if proj.ends_with(&compound_project_names[0][1]) {
-                    output.push_str(&proj.display().to_string());
-                    output.push('\n');
-                };
-                if proj.ends_with(&compound_project_names[0][2]) {
-                    output.push_str(&proj.display().to_string());
-                    output.push('\n');
-                };
-            }
-        } else {
-            for proj in &self.dir_items {
-                let matching_project = proj.ends_with(project_name);
-                if matching_project {
-                    output.push_str(&proj.display().to_string());
-                }
+            for proj in self.compound_project_paths(compound_project)? {
+                output.push_str(&proj.display().to_string());
+                output.push('\n');
             }
+        } else if let Some(proj) = self.matching_project(project_name)? {
+            output.push_str(&proj.display().to_string());
         }
         if output.is_empty() {
-            return None;
+            return Ok(None);
+        }
+        Ok(Some(output))
+    }
+
+    /// Same projects as `print_project_path` but as records. Members are
+    /// listed for a compound project
+    pub fn matching_records(&self, project_name: &str) -> Result<Vec<Record>> {
+        if let Some(compound_project) = self.compound_project(project_name) {
+            let paths = self.compound_project_paths(compound_project)?;
+            return Ok(paths.iter().map(|p| self.project_record(p)).collect());
+        }
+        let proj = self.matching_project(project_name)?;
+        Ok(proj.iter().map(|p| self.project_record(p)).collect())
+    }
+
+    pub fn compound_project(&self, project_name: &str) -> Option<&CompoundProject> {
+        // duplicates in config are ignored, only the first one is used
+        self.config
+            .compound_projects
+            .iter()
+            .find(|f| f.name == project_name)
+    }
+
+    /// Paths of the projects in a compound project in their order. A project
+    /// listed more than once, even by another name, is only opened once
+    pub fn compound_project_paths(
+        &self,
+        compound_project: &CompoundProject,
+    ) -> Result<Vec<&PathBuf>> {
+        let mut paths: Vec<&PathBuf> = Vec::new();
+        for name in &compound_project.projects {
+            let path = self.matching_project(name)?.ok_or(Error::Any(format!(
+                "Compound project '{}' references unknown project '{}'",
+                compound_project.name, name
+            )))?;
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+
+    pub fn project_id(&self, path: &PathBuf) -> String {
+        match self.ids.get(path) {
+            Some(id) => id.to_owned(),
+            None => get_file_name(path),
         }
-        Some(output)
     }
 
-    pub fn matching_project(&self, project_name: &str) -> Option<&PathBuf> {
-        for proj in &self.dir_items {
-            let matching_project = proj.ends_with(project_name);
-            if matching_project {
-                return Some(proj);
+    /// Finds a project by its id, name or any trailing part of its path like
+    /// `rust/utils`. Errors if more than one project matches
+    pub fn matching_project(&self, project_name: &str) -> Result<Option<&PathBuf>> {
+        if let Some(proj) = self.dir_items.iter().find(|p| self.ids[*p] == project_name) {
+            return Ok(Some(proj));
+        }
+        let matches = self
+            .dir_items
+            .iter()
+            .filter(|proj| proj.ends_with(project_name))
+            .collect::<Vec<_>>();
+        match matches[..] {
+            [] => Ok(None),
+            [proj] => Ok(Some(proj)),
+            _ => {
+                let ids = matches
+                    .iter()
+                    .map(|p| self.project_id(p))
+                    .collect::<Vec<_>>();
+                Err(Error::Any(format!(
+                    "'{project_name}' matches more than one project, use one of {}",
+                    ids.join(", ")
+                )))
             }
         }
-        None
+    }
+
+    /// `language_dir` of a project, the first dir under the root it was found
+    /// under even for nested projects. Included projects have none
+    pub fn project_lang(&self, path: &Path) -> Option<String> {
+        let root = once(&self.config.projects_root)
+            .chain(self.config.extra_roots.iter())
+            .find(|root| path.starts_with(root))?;
+        let mut dirs = path.strip_prefix(root).ok()?.components();
+        let lang = dirs.next()?;
+        // a project right in the root isn't in a language dir
+        dirs.next()?;
+        Some(lang.as_os_str().to_string_lossy().to_string())
+    }
+
+    /// Projects under a root are reported as such even if they are also included
+    pub fn project_source(&self, path: &Path) -> ProjectSource {
+        if path.starts_with(&self.config.projects_root) {
+            ProjectSource::Root
+        } else if self.config.extra_roots.iter().any(|r| path.starts_with(r)) {
+            ProjectSource::ExtraRoot
+        } else {
+            ProjectSource::Include
+        }
+    }
+
+    pub fn project_record(&self, path: &Path) -> Record {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        vec![
+            ("name", Field::from(name.as_ref())),
+            ("path", Field::from(path.display().to_string())),
+            ("source", Field::from(self.project_source(path).as_str())),
+            ("id", Field::from(self.ids.get(path).map(String::as_str))),
+        ]
+    }
+
+    /// Records of every project & compound project, sorted by name
+    pub fn records(&self) -> Vec<Record> {
+        let compound_records = self.config.compound_projects.iter().map(|c| {
+            vec![
+                ("name", Field::from(c.name.as_str())),
+                ("path", Field::Null),
+                ("source", Field::from(ProjectSource::Compound.as_str())),
+                ("id", Field::from(c.name.as_str())),
+            ]
+        });
+        let mut records = self
+            .dir_items
+            .iter()
+            .map(|path| self.project_record(path))
+            .chain(compound_records)
+            .collect::<Vec<_>>();
+        records.sort_by_cached_key(|record| match &record[0].1 {
+            Field::Str(name) => name.to_lowercase(),
+            _ => String::new(),
+        });
+        records
     }
 
     pub fn select_initial(&mut self) {
         self.selected_idx = 0;
     }
     pub fn select_next(&mut self) {
-        if self.selected_idx < (self.filtered_items.len() - 1) {
+        if self.selected_idx + 1 < self.filtered_items.len() {
             self.selected_idx += 1;
         }
     }
@@ -204,14 +415,10 @@ impl Projects {
     pub fn display_fmt(&self, from: usize, upto: usize) -> String {
         let mut output = String::new();
         for (idx, item) in self.filtered_items[from..upto].iter().enumerate() {
-            if self.cli_no_arg {
-                if idx == self.selected_idx - from {
-                    output.push_str(">> ");
-                } else {
-                    output.push_str("   ");
-                }
+            match self.matched_indices.get(item) {
+                Some(indices) => output.push_str(&highlight_matches(item, indices, &Style::new())),
+                None => output.push_str(item),
             }
-            output.push_str(item);
             if idx < (self.filtered_items.len() - 1) {
                 output.push('\n');
             }
@@ -219,88 +426,85 @@ impl Projects {
         output
     }
 
-    pub fn select_ui_fmt(&self) -> String {
-        let (from, upto) = match self.buffer_rows > self.filtered_items.len() {
-            true => (0, self.filtered_items.len()),
-            false => {
-                let overflow_at_start =
-                    self.filtered_items.len() - self.selected_idx < self.buffer_rows;
-                let overflow_at_end =
-                    self.selected_idx + self.buffer_rows < self.filtered_items.len();
-
-                let from = match overflow_at_start {
-                    true => self.filtered_items.len() - self.buffer_rows,
-                    false => self.selected_idx,
-                };
-                let upto = match overflow_at_end {
-                    true => self.selected_idx + self.buffer_rows,
-                    false => self.filtered_items.len(),
-                };
-                (from, upto)
-            }
-        };
-        self.display_fmt(from, upto)
+    fn open_in_terminal(&self, name: &str, paths: Vec<&PathBuf>) -> Result<()> {
+        let ids = paths
+            .into_iter()
+            .map(|path| (self.project_id(path), path))
+            .collect::<Vec<_>>();
+        // ids are unique, so `op <id>` always opens the intended project
+        let tabs = ids
+            .iter()
+            .map(|(name, path)| TabProject { name, path })
+            .collect::<Vec<_>>();
+        self.config.terminal.open(name, &tabs, &TermEnv::current())
     }
 
-    fn open_project_wezterm_cli(&self, project_name: &String) -> Result<String> {
-        // opens project in a wezterm new tab
-        let wezterm = Command::new("Wezterm")
-            .arg("cli")
-            .arg("spawn")
-            .arg("--")
-            .arg("op")
-            .arg(project_name)
-            .output()?;
-        String::from_utf8(wezterm.stdout)
-            .map_err(|_| Error::Any("Failed to parse output from 'wezterm spawn'".to_owned()))
+    fn open_compound_projects(&self, compound_project: &CompoundProject) -> Result<()> {
+        // fail early instead of opening only some of the projects
+        let paths = self.compound_project_paths(compound_project)?;
+        self.open_in_terminal(&compound_project.name, paths)
     }
 
-    fn open_compound_projects(&self, projects: &[String; 3]) -> Result<()> {
-        if exec_check::executable_exists("wezterm") && exec_check::executable_exists("op") {
-            self.open_project_wezterm_cli(&projects[1])?;
-            self.open_project_wezterm_cli(&projects[2])?;
-            return Ok(());
+    /// Paths of the given projects, compound projects are expanded to the
+    /// projects in them
+    pub fn selection_paths(&self, project_names: &[String]) -> Result<Vec<&PathBuf>> {
+        let mut paths: Vec<&PathBuf> = Vec::new();
+        for name in project_names {
+            let found = match self.compound_project(name) {
+                Some(compound_project) => self.compound_project_paths(compound_project)?,
+                None => vec![self
+                    .matching_project(name)?
+                    .ok_or(Error::Any(format!("Unknown project '{name}'")))?],
+            };
+            for path in found {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
         }
-        Err(Error::Any("Required executables missing".to_owned()))
+        Ok(paths)
     }
 
-    pub fn open_project_in_nvim(&self, project_name: &str) -> Result<()> {
-        // Error check
-        exec_check::executable_exists("nvim")
-            .then_some(true)
-            .ok_or(Error::Any("Missing nvim executable".to_owned()))?;
+    /// Opens projects in the terminal like a compound project made on the fly
+    pub fn open_selection(&self, project_names: &[String]) -> Result<()> {
+        let paths = self.selection_paths(project_names)?;
+        for path in &paths {
+            // best effort, like for a single project
+            let _ = History::record(path);
+        }
+        self.open_in_terminal(SELECTION_NAME, paths)
+    }
 
+    pub fn open_project_in_editor(&self, project_name: &str) -> Result<()> {
         // `project_name` exists in compound_projects
-        let compound_project_names = self
-            .config
-            .compound_projects
-            .iter()
-            .filter(|f| f[0] == project_name)
-            .collect::<Vec<_>>();
-
-        if !compound_project_names.is_empty() {
-            // More than length 1 here implies there are duplicates
-            // in config
-            // TODO: Try to handle this case
-            self.open_compound_projects(compound_project_names[0])?;
+        if let Some(compound_project) = self.compound_project(project_name) {
+            self.open_compound_projects(compound_project)?;
             return Ok(());
         }
 
         // `project_name` doesn't exist in compound_projects
-        if let Some(proj) = self.matching_project(project_name) {
-            println!("Opening project {:?}", project_name);
-
-            std::env::set_current_dir(proj)?;
-
-            let mut nvim_process = Command::new("nvim");
-            nvim_process.arg(".");
-            nvim_process.status()?;
-            println!("Closing project {:?}", project_name);
-            std::process::exit(0);
+        if let Some(proj) = self.matching_project(project_name)? {
+            self.open_path_in_editor(proj, project_name)?;
         } else {
             eprintln!("No matching projects found. Only below projects are available");
             eprintln!("{}", self.display_fmt(0, self.filtered_items.len()));
         }
         Ok(())
     }
+
+    /// Opens `proj` in editor or its tmux session. `proj` doesn't have to be
+    /// one of the discovered projects
+    pub fn open_path_in_editor(&self, proj: &PathBuf, project_name: &str) -> Result<()> {
+        let editor = Editor::resolve(&self.config, &get_file_name(proj), proj)?;
+        // best effort, failing to write history shouldn't stop opening a project
+        let _ = History::record(proj);
+        if self.config.sessionizer {
+            let name = self.project_id(proj);
+            return tmux_sessionize(&name, proj, &editor.command_line()?, &TermEnv::current());
+        }
+        println!("Opening project {:?}", project_name);
+        editor.launch(proj)?;
+        println!("Closing project {:?}", project_name);
+        std::process::exit(0);
+    }
 }
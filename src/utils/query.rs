@@ -0,0 +1,162 @@
+use super::fuzzy::{same_letter, scored_fuzzy_search_cased};
+
+////////////////////////////////////////////////////////////////////////////////
+// Search syntax of the select UI, similar to fzf
+////////////////////////////////////////////////////////////////////////////////
+//
+// `rs ^op` : every space separated term has to match
+// `op`     : fuzzy match
+// `'op`    : contains `op`
+// `^op`    : starts with `op`
+// `op$`    : ends with `op`
+// `!op`    : doesn't contain `op`, also `!^op`, `!op$` & `!@rust`
+// `@rust`  : project is in `rust` language dir, same as `lang:rust`
+//
+// Matching ignores case unless there is an upper case letter in the query
+
+/// Score & char positions of the matched characters
+pub type QueryMatch = (i64, Vec<usize>);
+
+#[derive(Debug, PartialEq)]
+pub enum TermKind {
+    Fuzzy,
+    Exact,
+    Prefix,
+    Suffix,
+    // both `^` & `$`
+    Equal,
+    Lang,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Term {
+    pub kind: TermKind,
+    pub text: String,
+    pub negate: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Query {
+    pub terms: Vec<Term>,
+    pub case_sensitive: bool,
+}
+
+fn parse_term(term: &str) -> Option<Term> {
+    let (negate, term) = match term.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, term),
+    };
+    let lang = term
+        .strip_prefix('@')
+        .or_else(|| term.strip_prefix("lang:"));
+    let (kind, text) = if let Some(lang) = lang {
+        (TermKind::Lang, lang)
+    } else if let Some(exact) = term.strip_prefix('\'') {
+        (TermKind::Exact, exact)
+    } else {
+        let prefix = term.strip_prefix('^');
+        let text = prefix.unwrap_or(term);
+        match (prefix.is_some(), text.strip_suffix('$')) {
+            (true, Some(text)) => (TermKind::Equal, text),
+            (true, None) => (TermKind::Prefix, text),
+            (false, Some(text)) => (TermKind::Suffix, text),
+            // negated terms aren't fuzzy, nearly everything would match them
+            (false, None) if negate => (TermKind::Exact, text),
+            (false, None) => (TermKind::Fuzzy, text),
+        }
+    };
+    // a lone `!`, `^` or `'` while typing matches everything
+    (!text.is_empty()).then(|| Term {
+        kind,
+        text: text.to_owned(),
+        negate,
+    })
+}
+
+/// Position of `text` in `item`
+fn find(item: &[char], text: &[char], case_sensitive: bool) -> Option<usize> {
+    item.windows(text.len()).position(|window| {
+        window
+            .iter()
+            .zip(text)
+            .all(|(a, b)| same_letter(*a, *b, case_sensitive))
+    })
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Self {
+        let terms = query
+            .split_whitespace()
+            .filter_map(parse_term)
+            .collect::<Vec<_>>();
+        // smart case, language dirs don't count
+        let case_sensitive = terms
+            .iter()
+            .filter(|term| term.kind != TermKind::Lang)
+            .any(|term| term.text.chars().any(char::is_uppercase));
+        Self {
+            terms,
+            case_sensitive,
+        }
+    }
+
+    /// Score & positions of a term in `item`, `None` if it doesn't match
+    fn match_term(&self, term: &Term, item: &str, lang: Option<&str>) -> Option<QueryMatch> {
+        let chars = item.chars().collect::<Vec<_>>();
+        let text = term.text.chars().collect::<Vec<_>>();
+        let cased = self.case_sensitive;
+        let same = |a: &[char], b: &[char]| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_letter(*a, *b, cased))
+        };
+        // earlier matches rank higher like leading letters of fuzzy matches
+        let start = match term.kind {
+            TermKind::Fuzzy => {
+                let (matched, score, indices) = match cased {
+                    true => scored_fuzzy_search_cased(&term.text, item, true),
+                    false => {
+                        // lower cased char by char, some chars are longer in lower
+                        // case like `İ` and indices would point past them
+                        let lower = item
+                            .chars()
+                            .map(|ch| ch.to_lowercase().next().unwrap_or(ch))
+                            .collect::<String>();
+                        scored_fuzzy_search_cased(&term.text, &lower, false)
+                    }
+                };
+                return matched.then_some((score, indices));
+            }
+            TermKind::Lang => {
+                let lang = lang?.chars().collect::<Vec<_>>();
+                return same(&lang, &text).then_some((0, Vec::new()));
+            }
+            TermKind::Exact => find(&chars, &text, cased)?,
+            TermKind::Prefix => same(chars.get(..text.len())?, &text).then_some(0)?,
+            TermKind::Suffix => {
+                let start = chars.len().checked_sub(text.len())?;
+                same(&chars[start..], &text).then_some(start)?
+            }
+            TermKind::Equal => same(&chars, &text).then_some(0)?,
+        };
+        Some((-(start as i64), (start..start + text.len()).collect()))
+    }
+
+    /// Sum of scores & all matched positions if every term matches `item`.
+    /// `lang` is the language dir of the project, if any
+    pub fn matches(&self, item: &str, lang: Option<&str>) -> Option<QueryMatch> {
+        let mut score = 0;
+        let mut indices = Vec::new();
+        for term in &self.terms {
+            match (self.match_term(term, item, lang), term.negate) {
+                (Some((term_score, term_indices)), false) => {
+                    score += term_score;
+                    indices.extend(term_indices);
+                }
+                (None, true) => {}
+                _ => return None,
+            }
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        Some((score, indices))
+    }
+}
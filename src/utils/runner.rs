@@ -0,0 +1,197 @@
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::process::output_until;
+use crate::error::{Error, Result};
+
+////////////////////////////////////////////////////////////////////////////////
+// Parallel job runner
+////////////////////////////////////////////////////////////////////////////////
+//
+// Runs a job for every input on a bounded number of threads. Results are
+// returned in the same order as inputs. Jobs can't be killed from outside, so
+// timeouts & Ctrl-C are cooperative. Processes started with `Job::output` are
+// killed as soon as their job times out or is cancelled
+
+static CANCELLED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Set once Ctrl-C is pressed. The first Ctrl-C lets running jobs wind down,
+/// a second one exits right away
+fn cancel_flag() -> Arc<AtomicBool> {
+    CANCELLED
+        .get_or_init(|| {
+            let flag = Arc::new(AtomicBool::new(false));
+            let handler_flag = flag.clone();
+            // best effort, Ctrl-C just kills the program without a handler
+            let _ = ctrlc::set_handler(move || {
+                if handler_flag.swap(true, Ordering::SeqCst) {
+                    std::process::exit(130);
+                }
+            });
+            flag
+        })
+        .clone()
+}
+
+#[derive(Debug, PartialEq)]
+pub enum JobResult<O> {
+    Done(O),
+    TimedOut,
+    // never started or interrupted by Ctrl-C
+    Cancelled,
+}
+
+/// Handed to every job to check whether it should stop early
+pub struct Job {
+    deadline: Option<Instant>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Job {
+    /// A job outside of a runner, e.g a single git command which shouldn't
+    /// block for long
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            deadline: Some(Instant::now() + timeout),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Runs `command` with its output captured. `None` if it was killed
+    /// because the job timed out or was cancelled
+    pub fn output(&self, command: &mut Command) -> std::io::Result<Option<Output>> {
+        output_until(command, || self.is_cancelled() || self.is_timed_out())
+    }
+}
+
+struct Progress {
+    label: String,
+    total: usize,
+    enabled: bool,
+}
+
+impl Progress {
+    fn update(&self, done: usize) {
+        if self.enabled {
+            eprint!("\r\x1b[2K[{done}/{}] {}", self.total, self.label);
+            let _ = std::io::stderr().flush();
+        }
+    }
+
+    fn clear(&self) {
+        if self.enabled {
+            eprint!("\r\x1b[2K");
+            let _ = std::io::stderr().flush();
+        }
+    }
+}
+
+pub struct Runner {
+    // max jobs running at a time
+    pub jobs: usize,
+    pub timeout: Option<Duration>,
+    // shown on stderr as `[done/total] label` when set
+    pub progress: Option<String>,
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Self {
+            jobs: thread::available_parallelism().map_or(4, |n| n.get()),
+            timeout: None,
+            progress: None,
+        }
+    }
+}
+
+impl Runner {
+    /// Runs `job` for every input, results are in the same order as `inputs`.
+    /// A panic in a job is propagated
+    pub fn run<I, O, F>(&self, inputs: Vec<I>, job: F) -> Result<Vec<JobResult<O>>>
+    where
+        I: Send,
+        O: Send,
+        F: Fn(I, &Job) -> O + Sync,
+    {
+        let total = inputs.len();
+        let cancelled = cancel_flag();
+        let progress = Progress {
+            label: self.progress.clone().unwrap_or_default(),
+            total,
+            enabled: self.progress.is_some() && std::io::stderr().is_terminal(),
+        };
+
+        // inputs are handed out in order, each one is taken by a single worker
+        let inputs = inputs
+            .into_iter()
+            .map(|input| std::sync::Mutex::new(Some(input)))
+            .collect::<Vec<_>>();
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+
+        let mut results = (0..total).map(|_| None).collect::<Vec<_>>();
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.clamp(1, total.max(1)) {
+                let sender = sender.clone();
+                let (inputs, next, job, cancelled) = (&inputs, &next, &job, &cancelled);
+                scope.spawn(move || loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(slot) = inputs.get(idx) else {
+                        break;
+                    };
+                    let input = slot
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .take()
+                        .expect("every input is taken once");
+                    let result = if cancelled.load(Ordering::SeqCst) {
+                        JobResult::Cancelled
+                    } else {
+                        let handle = Job {
+                            deadline: self.timeout.map(|timeout| Instant::now() + timeout),
+                            cancelled: cancelled.clone(),
+                        };
+                        let output = job(input, &handle);
+                        if handle.is_cancelled() {
+                            JobResult::Cancelled
+                        } else if handle.is_timed_out() {
+                            JobResult::TimedOut
+                        } else {
+                            JobResult::Done(output)
+                        }
+                    };
+                    if sender.send((idx, result)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+
+            progress.update(0);
+            // ends once every worker is done & has dropped its sender
+            for (done, (idx, result)) in receiver.iter().enumerate() {
+                results[idx] = Some(result);
+                progress.update(done + 1);
+            }
+            progress.clear();
+        });
+
+        results
+            .into_iter()
+            .map(|result| result.ok_or(Error::Any("A job panicked while running".to_owned())))
+            .collect()
+    }
+}
@@ -1,114 +1,409 @@
-use console::{Key, Term};
+use std::collections::HashMap;
+use std::fs::{read_dir, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
 
+use console::{pad_str, truncate_str, Alignment, Key, Style, Term};
+
+use super::fuzzy::highlight_matches;
+use super::history::History;
 use super::projects::Projects;
+use super::runner::Job;
+use crate::actions::git_status::git_status;
 use crate::error::{Error, Result};
 use crate::Config;
 
-fn clear_prev_screen(projects: &Projects, term: &Term) -> Result<()> {
-    let lines_to_clear = match projects.buffer_rows < projects.filtered_items.len() {
-        true => projects.buffer_rows + 1,
-        false => projects.filtered_items.len(),
+////////////////////////////////////////////////////////////////////////////////
+// Full screen select UI
+////////////////////////////////////////////////////////////////////////////////
+//
+// Find: <filter>
+// >> project_a         | /path/to/project_a
+//    project_b         |
+//    ...               | Branch: main -> origin/main
+//                      | ...
+// 2/42  ...key hints
+//
+// Drawn in the alternate screen so the shell is left as it was. Every key
+// redraws the whole screen with the current terminal size. Keys are read on
+// their own thread, the size is checked while waiting for one so a resize is
+// drawn right away
+
+// narrower terminals only show the list
+const MIN_PREVIEW_COLS: usize = 60;
+// git in a preview shouldn't freeze the UI, e.g on a network drive
+const PREVIEW_GIT_TIMEOUT: Duration = Duration::from_secs(2);
+const PREVIEW_SEPARATOR: &str = " │ ";
+// the pane is never taller than the terminal
+const README_LINES: usize = 200;
+// how often the terminal size is checked while waiting for a key
+const RESIZE_POLL: Duration = Duration::from_millis(100);
+
+/// Restores the main screen & cursor when dropped, even on errors
+struct AltScreen<'a> {
+    term: &'a Term,
+}
+
+impl<'a> AltScreen<'a> {
+    fn enter(term: &'a Term) -> Result<Self> {
+        term.write_str("\x1b[?1049h")?;
+        term.hide_cursor()?;
+        Ok(Self { term })
+    }
+}
+
+impl Drop for AltScreen<'_> {
+    fn drop(&mut self) {
+        let _ = self.term.show_cursor();
+        let _ = self.term.write_str("\x1b[?1049l");
+    }
+}
+
+/// First visible row of a list of `len` items, so `selected` stays in view of
+/// `rows` rows. Scrolls as little as possible from `offset`
+pub fn scroll_offset(selected: usize, offset: usize, rows: usize, len: usize) -> usize {
+    let rows = rows.max(1);
+    let offset = if selected < offset {
+        selected
+    } else if selected >= offset + rows {
+        selected + 1 - rows
+    } else {
+        offset
     };
-    term.clear_to_end_of_screen()?;
-    term.clear_last_lines(lines_to_clear)?;
-    Ok(())
+    // no empty rows at the end while there are items above
+    offset.min(len.saturating_sub(rows))
+}
+
+/// Name & first lines of the README in `path`, the rest isn't read
+pub fn readme_head(path: &Path) -> Option<(String, Vec<String>)> {
+    let readme = read_dir(path)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .find(|path| {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase());
+            name.is_some_and(|name| name == "readme" || name.starts_with("readme."))
+        })?;
+    let lines = BufReader::new(File::open(&readme).ok()?)
+        .split(b'\n')
+        .take(README_LINES)
+        .map_while(|line| line.ok())
+        .map(|line| {
+            let line = String::from_utf8_lossy(&line);
+            line.strip_suffix('\r').unwrap_or(&line).to_owned()
+        })
+        .collect();
+    let name = readme.file_name()?.to_string_lossy().to_string();
+    Some((name, lines))
 }
 
-// TODO: move select UI related functions from projects to here
-fn filter_print(
-    projects: &mut Projects,
-    filter_string: Option<&String>,
-    term: &Term,
-) -> Result<()> {
-    clear_prev_screen(projects, term)?;
-
-    if let Some(filter_string) = filter_string {
-        term.clear_last_lines(1)?; // this is to clear the previous `Find: <>`
-        println!("Find: {filter_string}");
-        projects.select_initial();
-        projects.filtered_items = projects
-            .filter_project_list(filter_string)
+fn last_commit(path: &Path, job: &Job) -> Option<String> {
+    let mut git = Command::new("git");
+    git.arg("-C")
+        .arg(path)
+        .args(["log", "-1", "--format=%h %s (%cr, %an)"]);
+    let output = job.output(&mut git).ok()??;
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    (output.status.success() && !commit.is_empty()).then_some(commit)
+}
+
+fn project_preview(path: &Path) -> Vec<String> {
+    let mut lines = vec![path.display().to_string(), String::new()];
+
+    let job = Job::with_timeout(PREVIEW_GIT_TIMEOUT);
+    match git_status(path, &job) {
+        Some(status) => {
+            lines.push(format!("Branch: {}", status.branch_summary()));
+            let details = status.details();
+            match details.is_empty() {
+                true => lines.push("Status: clean".to_owned()),
+                false => lines.push(format!("Status: {}", details.join(", "))),
+            }
+            if let Some(commit) = last_commit(path, &job) {
+                lines.push(format!("Commit: {commit}"));
+            }
+        }
+        None => lines.push("Not a git repository".to_owned()),
+    }
+
+    if let Some((name, readme)) = readme_head(path) {
+        lines.push(String::new());
+        lines.push(name);
+        lines.push("-".repeat(20));
+        // escape sequences in a README shouldn't mess with the screen
+        lines.extend(readme.iter().map(|line| {
+            line.replace('\t', "    ")
+                .chars()
+                .filter(|ch| !ch.is_control())
+                .collect()
+        }));
+    }
+    lines
+}
+
+struct Picker {
+    projects: Projects,
+    filter: String,
+    // first visible row of the list
+    offset: usize,
+    // number of projects without a filter
+    total: usize,
+    // preview lines by project id, git is only run once per project
+    previews: HashMap<String, Vec<String>>,
+    // ids marked with tab in the order they were marked
+    marked: Vec<String>,
+    // unstyled text for the terminal the UI is drawn on
+    plain: Style,
+}
+
+impl Picker {
+    fn apply_filter(&mut self) {
+        self.projects.select_initial();
+        self.offset = 0;
+        let matches = self.projects.filter_project_list(&self.filter);
+        self.projects.filtered_items = matches.iter().map(|v| v.0.to_owned()).collect();
+        self.projects.matched_indices = matches
             .into_iter()
-            .map(|v| v.0.to_owned())
+            .map(|(item, (_, indices))| (item, indices))
             .collect();
     }
 
-    if !projects.filtered_items.is_empty() {
-        println!("{}", projects.select_ui_fmt());
+    fn selected(&self) -> Option<&String> {
+        self.projects.filtered_items.get(self.projects.selected_idx)
+    }
+
+    fn toggle_mark(&mut self) {
+        if let Some(id) = self.selected().cloned() {
+            match self.marked.iter().position(|marked| *marked == id) {
+                Some(idx) => {
+                    self.marked.remove(idx);
+                }
+                None => self.marked.push(id),
+            }
+            self.projects.select_next();
+        }
+    }
+
+    /// Marked projects, otherwise the selected one
+    fn chosen(&self) -> Vec<String> {
+        match self.marked.is_empty() {
+            true => self.selected().cloned().into_iter().collect(),
+            false => self.marked.clone(),
+        }
     }
-    Ok(())
-}
 
-pub fn render_loop(config: Config) -> Result<()> {
-    let mut projects = Projects::new(config, true)?.catch_empty_project_list()?;
-    let mut filter_string = String::new();
-    let term = Term::stdout();
-    term.hide_cursor()?;
+    fn preview(&mut self) -> Vec<String> {
+        let Some(id) = self.selected().cloned() else {
+            return Vec::new();
+        };
+        if let Some(lines) = self.previews.get(&id) {
+            return lines.clone();
+        }
+        let lines = if let Some(compound) = self.projects.compound_project(&id) {
+            let mut lines = vec!["Compound project".to_owned(), String::new()];
+            lines.extend(compound.projects.iter().map(|name| format!("- {name}")));
+            lines
+        } else {
+            match self.projects.matching_project(&id) {
+                Ok(Some(path)) => project_preview(path),
+                _ => Vec::new(),
+            }
+        };
+        self.previews.insert(id, lines.clone());
+        lines
+    }
 
-    println!("Find: ");
-    println!("{}", projects.select_ui_fmt());
+    fn render(&mut self, term: &Term) -> Result<()> {
+        let (rows, cols) = term.size();
+        let (rows, cols) = (rows as usize, cols as usize);
+        // `Find:` line & key hints
+        let list_rows = rows.saturating_sub(2).max(1);
+        let len = self.projects.filtered_items.len();
+        self.offset = scroll_offset(self.projects.selected_idx, self.offset, list_rows, len);
 
-    'main: loop {
-        let read_key_raw = term.read_key_raw().expect("Failed to read key");
-        match read_key_raw {
-            Key::ArrowUp => {
-                projects.select_previous();
-                filter_print(&mut projects, None, &term)?;
+        let show_preview = cols >= MIN_PREVIEW_COLS;
+        let list_width = match show_preview {
+            true => cols * 2 / 5,
+            false => cols,
+        };
+        let preview_width = cols.saturating_sub(list_width + PREVIEW_SEPARATOR.chars().count());
+        let preview = match show_preview {
+            true => self.preview(),
+            false => Vec::new(),
+        };
+
+        let mut frame = String::from("\x1b[H");
+        let find = format!("Find: {}", self.filter);
+        frame.push_str(&truncate_str(&find, cols, ""));
+        frame.push_str("\x1b[K\r\n");
+
+        for row in 0..list_rows {
+            let idx = self.offset + row;
+            let selected = idx == self.projects.selected_idx;
+            let base = match selected {
+                true => self.plain.clone().bold(),
+                false => self.plain.clone(),
+            };
+            let item = match self.projects.filtered_items.get(idx) {
+                Some(item) => {
+                    let indices = self.projects.matched_indices.get(item);
+                    let marker = match (selected, self.marked.contains(item)) {
+                        (true, true) => ">* ",
+                        (true, false) => ">> ",
+                        (false, true) => " * ",
+                        (false, false) => "   ",
+                    };
+                    format!(
+                        "{}{}",
+                        base.apply_to(marker),
+                        highlight_matches(item, indices.map_or(&[], |i| i), &base)
+                    )
+                }
+                None => String::new(),
+            };
+            frame.push_str(&pad_str(&item, list_width, Alignment::Left, Some("…")));
+            if show_preview {
+                let line = preview.get(row).map_or("", |line| line.as_str());
+                let separator = self.plain.clone().dim().apply_to(PREVIEW_SEPARATOR);
+                frame.push_str(&separator.to_string());
+                frame.push_str(&truncate_str(line, preview_width, "…"));
             }
-            Key::ArrowDown => {
-                projects.select_next();
-                filter_print(&mut projects, None, &term)?;
+            frame.push_str("\x1b[K\r\n");
+        }
+
+        let marked = match self.marked.len() {
+            0 => String::new(),
+            count => format!(" ({count} marked)"),
+        };
+        let hints = format!(
+            "{}/{}{marked}  ↑↓ move · tab mark · enter open · esc quit · ctrl+backspace clear",
+            len, self.total
+        );
+        let hints = self
+            .plain
+            .clone()
+            .dim()
+            .apply_to(truncate_str(&hints, cols, ""));
+        frame.push_str(&hints.to_string());
+        frame.push_str("\x1b[K\x1b[J");
+        term.write_str(&frame)?;
+        Ok(())
+    }
+}
+
+/// Opens the chosen projects, or prints their paths if `print`
+pub fn render_loop(config: Config, print: bool) -> Result<()> {
+    let projects = Projects::new(config, true)?.catch_empty_project_list()?;
+    // stdout is captured when printing, like in `cd $(op --print)`
+    let (term, plain) = match print {
+        true => (Term::stderr(), Style::new().for_stderr()),
+        false => (Term::stdout(), Style::new()),
+    };
+    let mut picker = Picker {
+        total: projects.filtered_items.len(),
+        projects,
+        filter: String::new(),
+        offset: 0,
+        previews: HashMap::new(),
+        marked: Vec::new(),
+        plain,
+    };
+
+    // a key is only read when asked for, so none is taken from the editor
+    // opened afterwards
+    let (request_key, key_requests) = mpsc::channel::<()>();
+    let (send_key, keys) = mpsc::channel();
+    let key_term = term.clone();
+    thread::spawn(move || {
+        for () in key_requests {
+            if send_key.send(key_term.read_key_raw()).is_err() {
+                break;
             }
+        }
+    });
+
+    let screen = AltScreen::enter(&term)?;
+    let open = 'main: loop {
+        let mut size = term.size();
+        picker.render(&term)?;
+        let _ = request_key.send(());
+        let read_key_raw = loop {
+            match keys.recv_timeout(RESIZE_POLL) {
+                Ok(key) => break key.expect("Failed to read key"),
+                Err(RecvTimeoutError::Timeout) if term.size() != size => {
+                    size = term.size();
+                    picker.render(&term)?;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => panic!("Failed to read key"),
+            }
+        };
+        match read_key_raw {
+            Key::ArrowUp => picker.projects.select_previous(),
+            Key::ArrowDown => picker.projects.select_next(),
             Key::Char(ch) => match ch {
                 // clear content if ctrl+backspace is pressed
                 // NOTE: isn't tested for cmd or super keys
                 '\u{007f}' => {
-                    filter_string.clear();
-                    filter_print(&mut projects, Some(&filter_string), &term)?;
+                    picker.filter.clear();
+                    picker.apply_filter();
                 }
 
                 // move vertically when J/K is received instead of filtering
-                'J' => {
-                    projects.select_next();
-                    filter_print(&mut projects, None, &term)?;
-                }
-                'K' => {
-                    projects.select_previous();
-                    filter_print(&mut projects, None, &term)?;
-                }
+                'J' => picker.projects.select_next(),
+                'K' => picker.projects.select_previous(),
 
                 // do search and filter
                 _ => {
-                    filter_string.push(ch);
-                    filter_print(&mut projects, Some(&filter_string), &term)?;
+                    picker.filter.push(ch);
+                    picker.apply_filter();
                 }
             },
             Key::Backspace => {
-                if !filter_string.is_empty() {
-                    filter_string.pop();
-                }
-                filter_print(&mut projects, Some(&filter_string), &term)?;
-            }
-            Key::Enter => {
-                clear_prev_screen(&projects, &term)?;
-                select_project(projects)?;
-                break 'main;
-            }
-            Key::Escape => {
-                break 'main;
+                picker.filter.pop();
+                picker.apply_filter();
             }
+            Key::Tab => picker.toggle_mark(),
+            Key::Enter => break 'main true,
+            Key::Escape => break 'main false,
             _ => {}
         }
+    };
+    // editor takes over the main screen
+    drop(screen);
+
+    match (open, print) {
+        (true, true) => print_projects(&picker.projects, &picker.chosen()),
+        (true, false) => select_projects(&picker.projects, &picker.chosen()),
+        // scripts can tell nothing was chosen
+        (false, true) => Err(Error::Any("No project selected".to_owned())),
+        (false, false) => Ok(()),
     }
-    Ok(())
 }
 
-pub fn select_project(projects: Projects) -> Result<()> {
-    let projects = projects.catch_empty_project_list()?;
-    let project = projects
-        .filtered_items
-        .get(projects.selected_idx)
-        .ok_or(Error::NoProjectsFound)?;
-    projects.open_project_in_nvim(project)?;
+/// Paths of the projects one per line, compound projects are expanded
+fn print_projects(projects: &Projects, chosen: &[String]) -> Result<()> {
+    if chosen.is_empty() {
+        return Err(Error::NoProjectsFound);
+    }
+    for path in projects.selection_paths(chosen)? {
+        // chosen to `cd` into or such, it counts as a visit
+        let _ = History::record(path);
+        println!("{}", path.display());
+    }
     Ok(())
 }
+
+/// A single project opens in editor, more are opened in tabs of the terminal
+pub fn select_projects(projects: &Projects, chosen: &[String]) -> Result<()> {
+    match chosen {
+        [] => Err(Error::NoProjectsFound),
+        [project] => projects.open_project_in_editor(project),
+        _ => projects.open_selection(chosen),
+    }
+}
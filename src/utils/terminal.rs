@@ -0,0 +1,304 @@
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{Error, Result};
+
+////////////////////////////////////////////////////////////////////////////////
+// Terminal backends used to open compound projects
+////////////////////////////////////////////////////////////////////////////////
+//
+// Every project of a compound project is opened by running `op <project_name>`
+// in a new tab or window of the configured terminal/multiplexer
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TerminalBackend {
+    #[default]
+    Wezterm,
+    Tmux,
+    Zellij,
+    Kitty,
+}
+
+/// A project to be opened in a tab or window
+pub struct TabProject<'a> {
+    pub name: &'a str,
+    pub path: &'a Path,
+}
+
+/// Environment the backends run programs in
+#[derive(Debug, Default)]
+pub struct TermEnv {
+    // PATH programs are looked up in, the one of `op` when `None`
+    pub path: Option<OsString>,
+    // running inside tmux or zellij
+    pub tmux: bool,
+    pub zellij: bool,
+}
+
+impl TermEnv {
+    pub fn current() -> Self {
+        Self {
+            path: None,
+            tmux: std::env::var_os("TMUX").is_some(),
+            zellij: std::env::var_os("ZELLIJ").is_some(),
+        }
+    }
+
+    pub fn command(&self, program: &str) -> Command {
+        let mut command = Command::new(program);
+        if let Some(path) = &self.path {
+            command.env("PATH", path);
+        }
+        command
+    }
+
+    pub fn executable_exists(&self, program: &str) -> bool {
+        match &self.path {
+            Some(path) => std::env::split_paths(path).any(|dir| dir.join(program).is_file()),
+            None => exec_check::executable_exists(program),
+        }
+    }
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let output = command.output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Any(format!("'{program}' failed. {}", stderr.trim())));
+    }
+    Ok(())
+}
+
+fn attach(command: &mut Command) -> Result<()> {
+    // interactive commands which take over the current terminal
+    let program = command.get_program().to_string_lossy().to_string();
+    let status = command.status()?;
+    if !status.success() {
+        return Err(Error::Any(format!("'{program}' failed with {status}")));
+    }
+    Ok(())
+}
+
+/// tmux doesn't allow `.` and `:` in session names
+pub fn tmux_session_name(name: &str) -> String {
+    name.replace(['.', ':'], "_")
+}
+
+fn kdl_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn zellij_tab(project: &TabProject) -> String {
+    format!(
+        "    tab name={} cwd={} {{\n        pane command=\"op\" {{\n            args {}\n        }}\n    }}\n",
+        kdl_string(project.name),
+        kdl_string(&project.path.display().to_string()),
+        kdl_string(project.name)
+    )
+}
+
+/// Writes a layout to a new file in temp dir, concurrent calls or other users
+/// of the temp dir never share one
+fn zellij_layout(projects: &[TabProject]) -> Result<PathBuf> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let (mut layout, layout_path) = loop {
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let file_name = format!("op_zellij_{}_{count}.kdl", std::process::id());
+        let layout_path = std::env::temp_dir().join(file_name);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&layout_path)
+        {
+            Ok(file) => break (file, layout_path),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    };
+    writeln!(layout, "layout {{")?;
+    for project in projects {
+        write!(layout, "{}", zellij_tab(project))?;
+    }
+    writeln!(layout, "}}")?;
+    Ok(layout_path)
+}
+
+/// Switches to the tmux session of a project, creating it with `command`
+/// running in the project dir when it doesn't exist yet
+pub fn tmux_sessionize(name: &str, path: &Path, command: &[&str], env: &TermEnv) -> Result<()> {
+    if !env.executable_exists("tmux") {
+        return Err(Error::Any("Missing tmux executable".to_owned()));
+    }
+    let session = tmux_session_name(name);
+    let target = format!("={session}");
+    let session_exists = env
+        .command("tmux")
+        .args(["has-session", "-t", &target])
+        .output()?
+        .status
+        .success();
+    if !session_exists {
+        run(env
+            .command("tmux")
+            .args(["new-session", "-d", "-s", &session, "-c"])
+            .arg(path)
+            .args(command))?;
+    }
+
+    // a client can't be attached from inside tmux, current client is switched instead
+    match env.tmux {
+        true => run(env.command("tmux").args(["switch-client", "-t", &target])),
+        false => attach(env.command("tmux").args(["attach-session", "-t", &target])),
+    }
+}
+
+impl TerminalBackend {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wezterm" => Some(Self::Wezterm),
+            "tmux" => Some(Self::Tmux),
+            "zellij" => Some(Self::Zellij),
+            "kitty" => Some(Self::Kitty),
+            _ => None,
+        }
+    }
+
+    pub fn program(&self) -> &'static str {
+        match self {
+            Self::Wezterm => "wezterm",
+            Self::Tmux => "tmux",
+            Self::Zellij => "zellij",
+            Self::Kitty => "kitty",
+        }
+    }
+
+    fn open_wezterm(&self, projects: &[TabProject], env: &TermEnv) -> Result<()> {
+        for project in projects {
+            run(env
+                .command("wezterm")
+                .args(["cli", "spawn", "--cwd"])
+                .arg(project.path)
+                .args(["--", "op", project.name]))?;
+        }
+        Ok(())
+    }
+
+    fn open_tmux(&self, session: &str, projects: &[TabProject], env: &TermEnv) -> Result<()> {
+        let new_window = |project: &TabProject, target: Option<&str>| {
+            let mut tmux = env.command("tmux");
+            tmux.arg("new-window");
+            if let Some(target) = target {
+                tmux.args(["-t", target]);
+            }
+            tmux.args(["-n", project.name, "-c"])
+                .arg(project.path)
+                .args(["op", project.name]);
+            tmux
+        };
+
+        // already inside tmux, a window for each project in current session
+        if env.tmux {
+            for project in projects {
+                run(&mut new_window(project, None))?;
+            }
+            return Ok(());
+        }
+
+        // otherwise a new session named after the compound project
+        let session = tmux_session_name(session);
+        let target = format!("{session}:");
+        let session_exists = env
+            .command("tmux")
+            .args(["has-session", "-t", &format!("={session}")])
+            .output()?
+            .status
+            .success();
+        for (idx, project) in projects.iter().enumerate() {
+            if idx == 0 && !session_exists {
+                run(env
+                    .command("tmux")
+                    .args([
+                        "new-session",
+                        "-d",
+                        "-s",
+                        &session,
+                        "-n",
+                        project.name,
+                        "-c",
+                    ])
+                    .arg(project.path)
+                    .args(["op", project.name]))?;
+            } else {
+                run(&mut new_window(project, Some(&target)))?;
+            }
+        }
+        attach(env.command("tmux").args(["attach-session", "-t", &session]))
+    }
+
+    fn open_zellij(&self, session: &str, projects: &[TabProject], env: &TermEnv) -> Result<()> {
+        // already inside zellij, a tab for each project in current session
+        if env.zellij {
+            for project in projects {
+                let layout = zellij_layout(std::slice::from_ref(project))?;
+                let result = run(env
+                    .command("zellij")
+                    .args(["action", "new-tab", "--layout"])
+                    .arg(&layout));
+                let _ = std::fs::remove_file(layout);
+                result?;
+            }
+            return Ok(());
+        }
+
+        // otherwise a new session with all the projects as tabs
+        let layout = zellij_layout(projects)?;
+        let result = attach(
+            env.command("zellij")
+                .args(["--session", &tmux_session_name(session), "--layout"])
+                .arg(&layout),
+        );
+        let _ = std::fs::remove_file(layout);
+        result
+    }
+
+    fn open_kitty(&self, projects: &[TabProject], env: &TermEnv) -> Result<()> {
+        // needs `allow_remote_control` to be enabled in kitty
+        for project in projects {
+            run(env
+                .command("kitty")
+                .args([
+                    "@",
+                    "launch",
+                    "--type=tab",
+                    "--tab-title",
+                    project.name,
+                    "--cwd",
+                ])
+                .arg(project.path)
+                .args(["op", project.name]))?;
+        }
+        Ok(())
+    }
+
+    /// Opens every project in a new tab or window. `name` is used to name the
+    /// session when the backend has to create one
+    pub fn open(&self, name: &str, projects: &[TabProject], env: &TermEnv) -> Result<()> {
+        if !env.executable_exists(self.program()) || !env.executable_exists("op") {
+            return Err(Error::Any(format!(
+                "Required executables missing. Both '{}' and 'op' should be in PATH",
+                self.program()
+            )));
+        }
+        match self {
+            Self::Wezterm => self.open_wezterm(projects, env),
+            Self::Tmux => self.open_tmux(name, projects, env),
+            Self::Zellij => self.open_zellij(name, projects, env),
+            Self::Kitty => self.open_kitty(projects, env),
+        }
+    }
+}